@@ -1,23 +1,28 @@
 use crate::{
-    model::{constance::REQUEST_HEAD_TOKEN, state::AppState},
-    service::auth_service::verify_token_and_authority,
+    model::{error::AppError, state::AppState},
+    service::{
+        auth_service::{verify_token, verify_token_and_authority},
+        permission_service::{ACCESS_RULES, AccessLevel},
+    },
+    util::header_util::extract_token,
 };
 use actix_web::{
-    Error,
+    Error, HttpMessage,
     dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
-    error, web,
+    web,
 };
 use futures_util::future::LocalBoxFuture;
-use log::{debug, error};
+use log::{debug, error, warn};
 use std::future::{Ready, ready};
+use std::rc::Rc;
 
 pub struct AuthMiddleware<S> {
-    service: S,
+    service: Rc<S>,
 }
 
 impl<S, B> Service<ServiceRequest> for AuthMiddleware<S>
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -30,71 +35,68 @@ where
     fn call(&self, req: ServiceRequest) -> Self::Future {
         debug!("auth middleware");
 
-        // 访问路径
-        let path = req.path();
-        // 从header中获取token
-        let token_option = req.headers().get(REQUEST_HEAD_TOKEN);
-        // 从请求中获取应用数据
-        let app_data_option = req.app_data::<web::Data<AppState>>();
+        let service = Rc::clone(&self.service);
 
-        // 根据应用数据和Token的存在情况进行匹配处理
-        match (app_data_option, token_option) {
-            // 当应用数据和Token都存在时
-            (Some(app_data), Some(token)) => {
-                // 获取角色与资源的关系映射
-                let role_rel_resource_map = &app_data.role_rel_resource_map;
-
-                // 如果Token为空，则返回未授权错误
-                if token.is_empty() {
-                    Box::pin(async move { Err(error::ErrorUnauthorized("未授权")) })
-                } else {
-                    // 解析Token并验证权限
-                    match token
-                        .to_str()
-                        .map_err(|_| error::ErrorBadRequest("无效的Token格式"))
-                    {
-                        // 如果Token解析成功，则进行权限验证
-                        Ok(token_str) => {
-                            match verify_token_and_authority(token_str, path, role_rel_resource_map)
-                            {
-                                // 如果验证成功，则调用下一个服务
-                                Ok(_) => {
-                                    let fut = self.service.call(req);
+        Box::pin(async move {
+            // 访问方法与路径
+            let method = req.method().as_str().to_string();
+            let path = req.path().to_string();
+            // 公开路径无需令牌，直接调用下一个服务
+            let access_level = ACCESS_RULES.access_level(&method, &path);
+            if access_level == AccessLevel::Public {
+                debug!("公开路径: {} {}", method, path);
+                return service.call(req).await;
+            }
+            // 按配置的来源提取token
+            let token_option = extract_token(req.request());
+            // 从请求中获取应用数据
+            let app_data_option = req.app_data::<web::Data<AppState>>().cloned();
 
-                                    Box::pin(async move {
-                                        // 返回处理结果
-                                        let res = fut.await?;
-                                        // 记录日志，表示认证中间件处理完成
-                                        debug!("auth middleware done");
-                                        Ok(res)
-                                    })
-                                }
-                                // 如果验证失败，则返回禁止访问错误
-                                Err(err) => {
-                                    error!("权限验证失败: {}", err);
-                                    Box::pin(async move { Err(error::ErrorForbidden(err)) })
-                                }
-                            }
-                        }
-                        // 如果Token解析失败，则返回错误
+            // 根据应用数据和Token的存在情况进行匹配处理
+            match (app_data_option, token_option) {
+                // 当应用数据和Token都存在时
+                (Some(app_data), Some(token)) => {
+                    // 仅需登录的路径只验证Token，其余路径验证Token与权限
+                    let verified = if access_level == AccessLevel::Authenticated {
+                        verify_token(&app_data.db_pool, &token, &app_data.revocation_store).await
+                    } else {
+                        verify_token_and_authority(
+                            &app_data.db_pool,
+                            &token,
+                            &method,
+                            &path,
+                            &app_data.permission_matcher,
+                            &app_data.revocation_store,
+                        )
+                        .await
+                    };
+                    let claims = match verified {
+                        Ok(claims) => claims,
+                        // 如果验证失败，令牌无效返回401，权限不足返回403
                         Err(err) => {
-                            error!("Token解析失败: {}", err);
-                            Box::pin(async move { Err(err) })
+                            warn!("权限验证失败: {}", err);
+                            return Err(err.into());
                         }
-                    }
+                    };
+                    // 如果验证成功，保存令牌声明供后续处理函数使用，并调用下一个服务
+                    req.extensions_mut().insert(claims);
+                    let res = service.call(req).await?;
+                    // 记录日志，表示认证中间件处理完成
+                    debug!("auth middleware done");
+                    Ok(res)
+                }
+                // 当应用数据存在但Token不存在时，返回未授权错误
+                (Some(_), None) => {
+                    warn!("未提供Token");
+                    Err(AppError::Unauthorized("未授权".to_string()).into())
+                }
+                // 当应用数据不存在时，返回未获取到资源错误
+                (None, _) => {
+                    error!("未获取到资源");
+                    Err(AppError::Internal("未获取到资源".to_string()).into())
                 }
             }
-            // 当应用数据存在但Token不存在时，返回未授权错误
-            (Some(_), None) => {
-                error!("未提供Token");
-                Box::pin(async move { Err(error::ErrorUnauthorized("未授权")) })
-            }
-            // 当应用数据不存在时，返回未获取到资源错误
-            (None, _) => {
-                error!("未获取到资源");
-                Box::pin(async move { Err(error::ErrorUnauthorized("未获取到资源")) })
-            }
-        }
+        })
     }
 }
 
@@ -109,7 +111,7 @@ pub struct Auth;
 // `B` - type of response's body
 impl<S, B> Transform<S, ServiceRequest> for Auth
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -120,6 +122,8 @@ where
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ready(Ok(AuthMiddleware { service }))
+        ready(Ok(AuthMiddleware {
+            service: Rc::new(service),
+        }))
     }
 }
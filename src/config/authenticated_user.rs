@@ -0,0 +1,85 @@
+use crate::{
+    model::{error::AppError, state::AppState},
+    service::auth_service::verify_token,
+    util::{header_util::extract_token, token_util::TokenClaims},
+};
+use actix_web::{FromRequest, HttpMessage, HttpRequest, dev::Payload, web};
+use futures_util::future::LocalBoxFuture;
+use log::warn;
+
+/// 当前登录用户
+///
+/// 在处理函数的参数中声明即可获取当前用户。经过`Auth`中间件的请求直接使用中间件验证后的令牌声明；
+/// 未经过中间件的请求会自行提取并验证令牌。令牌缺失或无效时返回401
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub id: u64,
+    pub username: String,
+    pub roles: Vec<String>,
+    // 完整的令牌声明，用于退出登录等需要令牌ID的场景
+    pub claims: TokenClaims,
+}
+
+impl From<TokenClaims> for AuthenticatedUser {
+    fn from(claims: TokenClaims) -> Self {
+        AuthenticatedUser {
+            id: claims.user_id,
+            username: claims.username.clone(),
+            roles: claims.roles.clone(),
+            claims,
+        }
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = AppError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        // 中间件已验证过的令牌声明
+        if let Some(claims) = req.extensions().get::<TokenClaims>() {
+            let user = AuthenticatedUser::from(claims.clone());
+            return Box::pin(async move { Ok(user) });
+        }
+
+        let token_option = extract_token(req);
+        let app_data_option = req.app_data::<web::Data<AppState>>().cloned();
+        Box::pin(async move {
+            let (Some(token), Some(app_data)) = (token_option, app_data_option) else {
+                return Err(AppError::Unauthorized("未授权".to_string()));
+            };
+            let claims = verify_token(&app_data.db_pool, &token, &app_data.revocation_store)
+                .await
+                .inspect_err(|err| warn!("令牌验证失败: {}", err))?;
+            Ok(AuthenticatedUser::from(claims))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{ResponseError, http::StatusCode, test::TestRequest};
+
+    #[actix_rt::test]
+    async fn test_extract_authenticated_user() {
+        let req = TestRequest::default().to_http_request();
+        req.extensions_mut().insert(TokenClaims {
+            user_id: 1,
+            username: "test".to_string(),
+            exp: 0,
+            iat: 0,
+            jti: "jti".to_string(),
+            roles: vec!["admin".to_string()],
+            auth_version: 0,
+        });
+        let user = AuthenticatedUser::extract(&req).await.unwrap();
+        assert_eq!(1, user.id);
+        assert_eq!(vec!["admin".to_string()], user.roles);
+
+        // 没有令牌时返回401而不是panic
+        let req = TestRequest::default().to_http_request();
+        let err = AuthenticatedUser::extract(&req).await.unwrap_err();
+        assert_eq!(StatusCode::UNAUTHORIZED, err.status_code());
+    }
+}
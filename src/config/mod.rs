@@ -1,2 +1,3 @@
 pub mod auth_middleware;
+pub mod authenticated_user;
 pub mod logger;
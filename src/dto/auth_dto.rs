@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct LoginDto {
@@ -18,4 +19,157 @@ pub struct RegisterDto {
 pub struct RoleRelPathDto {
     pub role_name: String,
     pub resource_value: String,
+    // 允许的请求方法，逗号分隔；为空或 * 表示全部方法
+    pub resource_method: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RoleInheritDto {
+    pub role_name: String,
+    // 被继承的父角色名
+    pub parent_role_name: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct InvalidPatternDto {
+    pub role_name: String,
+    pub resource_value: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PermissionSnapshotDto {
+    // 角色名 -> 生效的资源规则
+    pub role_rel_resource: HashMap<String, Vec<RoleRelPathDto>>,
+    // 加载时被剔除的无效规则
+    pub invalid_patterns: Vec<InvalidPatternDto>,
+    // 角色名 -> 继承的全部祖先角色
+    pub role_inherit: HashMap<String, Vec<String>>,
+    // 检测到的循环继承，按继承顺序列出环上的角色
+    pub role_cycles: Vec<Vec<String>>,
+    // 规则对应的权限版本号
+    pub version: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PermissionVersionDto {
+    // 当前实例已加载的版本号
+    pub loaded_version: u64,
+    // 数据库中的最新版本号
+    pub current_version: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PermissionExplainQueryDto {
+    pub user_id: u64,
+    // 请求方法，为空时按 GET 处理
+    pub method: Option<String>,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PatternEvaluationDto {
+    // 规则所属的角色，可能是用户角色继承的祖先角色
+    pub role_name: String,
+    pub resource_value: String,
+    pub resource_method: Option<String>,
+    pub path_matched: bool,
+    pub method_matched: bool,
+}
+
+// 权限判定结果
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionDecision {
+    // 公开路径，无需令牌
+    Public,
+    // 仅需登录的路径，不校验资源权限
+    Authenticated,
+    // 账号已禁用，令牌校验不会通过
+    AccountDisabled,
+    // 命中资源规则
+    Granted,
+    // 未命中资源规则，但拥有超级管理员角色
+    SuperRole,
+    // 拒绝访问
+    Denied,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PermissionExplainDto {
+    pub user_id: u64,
+    pub username: Option<String>,
+    pub method: String,
+    pub path: String,
+    // 数据库中用户当前拥有的角色
+    pub roles: Vec<String>,
+    // 展开继承关系后参与校验的角色
+    pub expanded_roles: Vec<String>,
+    // 逐条评估的资源规则
+    pub evaluations: Vec<PatternEvaluationDto>,
+    // 第一条同时匹配路径与方法的规则
+    pub matched: Option<PatternEvaluationDto>,
+    pub decision: PermissionDecision,
+    pub allowed: bool,
+}
+
+/// 登录结果
+///
+/// 未启用两步验证时直接返回令牌对；启用时返回登录挑战，客户端需携带挑战令牌提交动态验证码
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginVo {
+    Token(TokenPairDto),
+    Challenge(TotpChallengeVo),
+}
+
+#[derive(Debug, Serialize)]
+pub struct TotpChallengeVo {
+    // 固定为 true，表示需要提交动态验证码
+    pub mfa_required: bool,
+    pub challenge_token: String,
+    // 挑战令牌的有效期，单位秒
+    pub expires_in: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TotpLoginDto {
+    pub challenge_token: String,
+    // 动态验证码与恢复码提供其一即可
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RefreshTokenDto {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TokenPairDto {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: i64, // 访问令牌剩余有效秒数
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LogoutDto {
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ChangePasswordDto {
+    pub old_password: String,
+    pub new_password: String,
+    pub recheck_password: String,
+}
+
+/// 当前登录用户的个人信息，角色为当前令牌中生效的角色
+#[derive(Debug, Serialize, Clone)]
+pub struct ProfileVo {
+    pub id: u64,
+    pub username: String,
+    pub name: String,
+    pub roles: Vec<String>,
+    pub totp_enabled: bool,
 }
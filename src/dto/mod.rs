@@ -1 +1,6 @@
 pub mod auth_dto;
+pub mod page_dto;
+pub mod resource_dto;
+pub mod role_dto;
+pub mod totp_dto;
+pub mod user_dto;
@@ -0,0 +1,49 @@
+use crate::model::constance::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use rbatis::{Page, PageRequest};
+use serde::Serialize;
+
+/// 分页结果
+#[derive(Debug, Serialize)]
+pub struct PageVo<T> {
+    pub records: Vec<T>,
+    pub total: u64,
+    pub page_no: u64,
+    pub page_size: u64,
+}
+
+impl<T> PageVo<T> {
+    /// 将数据库分页结果转换为分页响应，同时转换每条记录
+    pub fn from_page<S: Send + Sync>(page: Page<S>, f: impl FnMut(S) -> T) -> Self {
+        PageVo {
+            records: page.records.into_iter().map(f).collect(),
+            total: page.total,
+            page_no: page.page_no,
+            page_size: page.page_size,
+        }
+    }
+}
+
+/// 根据请求参数构建分页请求，页码从1开始，每页条数不超过`MAX_PAGE_SIZE`
+pub fn page_request(page_no: Option<u64>, page_size: Option<u64>) -> PageRequest {
+    let page_size = page_size
+        .filter(|size| *size > 0)
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .min(MAX_PAGE_SIZE);
+    PageRequest::new(page_no.unwrap_or(1).max(1), page_size)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rbatis::IPageRequest;
+
+    #[test]
+    fn test_page_request() {
+        let req = page_request(None, None);
+        assert_eq!((1, DEFAULT_PAGE_SIZE), (req.page_no(), req.page_size()));
+        let req = page_request(Some(0), Some(10_000));
+        assert_eq!((1, MAX_PAGE_SIZE), (req.page_no(), req.page_size()));
+        let req = page_request(Some(3), Some(20));
+        assert_eq!((3, 20), (req.page_no(), req.page_size()));
+    }
+}
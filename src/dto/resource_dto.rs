@@ -0,0 +1,83 @@
+use crate::model::user::Resource;
+use rbatis::rbdc::DateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ResourceQueryDto {
+    pub page_no: Option<u64>,
+    pub page_size: Option<u64>,
+    // 资源类型 1 path 2 page 3 button
+    pub resource_type: Option<u8>,
+    // 资源值，模糊匹配
+    pub resource_value: Option<String>,
+    pub parent_id: Option<u64>,
+}
+
+/// 创建或修改资源，修改时字段为空表示保持不变
+#[derive(Debug, Deserialize, Clone)]
+pub struct ResourceSaveDto {
+    pub resource_value: Option<String>,
+    pub resource_desc: Option<String>,
+    pub resource_type: Option<u8>,
+    pub resource_method: Option<String>,
+    pub parent_id: Option<u64>,
+    // 修改时为 true 表示取消上级资源，不能与 parent_id 同时提供
+    #[serde(default)]
+    pub clear_parent: bool,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ResourceGrantDto {
+    pub resource_ids: Vec<u64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ResourceVo {
+    pub id: u64,
+    pub resource_value: String,
+    pub resource_desc: Option<String>,
+    pub resource_type: u8,
+    pub resource_method: Option<String>,
+    pub parent_id: Option<u64>,
+    pub create_time: Option<DateTime>,
+    pub update_time: Option<DateTime>,
+}
+
+impl From<Resource> for ResourceVo {
+    fn from(resource: Resource) -> Self {
+        ResourceVo {
+            id: resource.id.unwrap_or_default(),
+            resource_value: resource.resource_value.unwrap_or_default(),
+            resource_desc: resource.resource_desc,
+            resource_type: resource.resource_type.unwrap_or_default(),
+            resource_method: resource.resource_method,
+            parent_id: resource.parent_id,
+            create_time: resource.create_time,
+            update_time: resource.update_time,
+        }
+    }
+}
+
+/// 菜单树节点，包含页面与按钮资源
+#[derive(Debug, Serialize, Clone)]
+pub struct MenuVo {
+    pub id: u64,
+    pub resource_value: String,
+    pub resource_desc: Option<String>,
+    pub resource_type: u8,
+    pub parent_id: Option<u64>,
+    pub children: Vec<MenuVo>,
+}
+
+impl From<Resource> for MenuVo {
+    fn from(resource: Resource) -> Self {
+        MenuVo {
+            id: resource.id.unwrap_or_default(),
+            resource_value: resource.resource_value.unwrap_or_default(),
+            resource_desc: resource.resource_desc,
+            resource_type: resource.resource_type.unwrap_or_default(),
+            parent_id: resource.parent_id,
+            children: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use crate::model::user::Role;
+use rbatis::rbdc::DateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RoleQueryDto {
+    pub page_no: Option<u64>,
+    pub page_size: Option<u64>,
+    // 角色名，模糊匹配
+    pub role_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RoleCreateDto {
+    pub role_name: String,
+    pub role_desc: Option<String>,
+}
+
+/// 修改角色，字段为空时保持不变
+#[derive(Debug, Deserialize, Clone)]
+pub struct RoleUpdateDto {
+    pub role_name: Option<String>,
+    pub role_desc: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RoleAssignDto {
+    pub user_ids: Vec<u64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RoleVo {
+    pub id: u64,
+    pub role_name: String,
+    pub role_desc: Option<String>,
+    pub create_time: Option<DateTime>,
+    pub update_time: Option<DateTime>,
+}
+
+impl From<Role> for RoleVo {
+    fn from(role: Role) -> Self {
+        RoleVo {
+            id: role.id.unwrap_or_default(),
+            role_name: role.role_name.unwrap_or_default(),
+            role_desc: role.role_desc,
+            create_time: role.create_time,
+            update_time: role.update_time,
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize)]
+pub struct TotpStatusVo {
+    pub enabled: bool,
+    // 剩余未使用的恢复码数量
+    pub recovery_codes_remaining: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TotpSetupVo {
+    // Base32编码的密钥，供无法扫码时手动输入
+    pub secret: String,
+    // 认证器应用扫码使用的 otpauth:// URI
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TotpCodeDto {
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TotpDisableDto {
+    pub password: String,
+    // 动态验证码与恢复码提供其一即可
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesVo {
+    // 恢复码明文只在生成时返回一次，每个恢复码只能使用一次
+    pub recovery_codes: Vec<String>,
+}
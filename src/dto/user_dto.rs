@@ -0,0 +1,51 @@
+use crate::model::constance::TOTP_ENABLED;
+use crate::model::user::User;
+use rbatis::rbdc::DateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct UserQueryDto {
+    pub page_no: Option<u64>,
+    pub page_size: Option<u64>,
+    // 用户名，模糊匹配
+    pub username: Option<String>,
+    // 姓名，模糊匹配
+    pub name: Option<String>,
+    pub status: Option<u8>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct UserUpdateDto {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ResetPasswordDto {
+    pub password: String,
+}
+
+/// 返回给客户端的用户信息，不包含密码
+#[derive(Debug, Serialize, Clone)]
+pub struct UserVo {
+    pub id: u64,
+    pub username: String,
+    pub name: String,
+    pub status: u8,
+    pub totp_enabled: bool,
+    pub create_time: Option<DateTime>,
+    pub update_time: Option<DateTime>,
+}
+
+impl From<User> for UserVo {
+    fn from(user: User) -> Self {
+        UserVo {
+            id: user.id.unwrap_or_default(),
+            username: user.username.unwrap_or_default(),
+            name: user.name.unwrap_or_default(),
+            status: user.status.unwrap_or_default(),
+            totp_enabled: user.totp_enabled == Some(TOTP_ENABLED),
+            create_time: user.create_time,
+            update_time: user.update_time,
+        }
+    }
+}
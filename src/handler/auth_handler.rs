@@ -1,9 +1,19 @@
-use crate::dto::auth_dto::{LoginDto, RegisterDto};
-use crate::model::user::User;
+use crate::config::authenticated_user::AuthenticatedUser;
+use crate::dto::auth_dto::{
+    ChangePasswordDto, LoginDto, LoginVo, LogoutDto, ProfileVo, RefreshTokenDto, RegisterDto,
+    TokenPairDto, TotpLoginDto,
+};
+use crate::dto::resource_dto::MenuVo;
+use crate::dto::totp_dto::{
+    RecoveryCodesVo, TotpCodeDto, TotpDisableDto, TotpSetupVo, TotpStatusVo,
+};
+use crate::dto::user_dto::UserVo;
+use crate::model::error::AppResult;
 use crate::model::{response::ResponseResult, state::AppState};
-use crate::service::auth_service;
-use actix_web::{HttpResponse, Responder, Result, post, web};
-use log::{error, info};
+use crate::service::{auth_service, resource_service, totp_service};
+use crate::util::{config_util::CFG, header_util::client_ip};
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web};
+use log::{info, warn};
 
 /// 异步处理用户登录请求
 ///
@@ -11,53 +21,60 @@ use log::{error, info};
 ///
 /// # 参数
 ///
+/// - `req`: 请求，用于获取客户端IP
 /// - `app_state`: 应用状态的共享数据，包含数据库连接池等信息
 /// - `login_dto`: 包含用户登录信息的数据传输对象
 ///
 /// # 返回
 ///
-/// - 登录成功时返回包含用户令牌的HttpResponse
-/// - 登录失败或令牌生成失败时返回相应的错误信息
+/// - 登录成功时返回包含访问令牌与刷新令牌的HttpResponse
+/// - 用户已启用两步验证时返回登录挑战，需调用 /auth/login/totp 提交动态验证码
+/// - 登录失败、被锁定或令牌生成失败时返回相应的错误
 #[post("/login")]
 pub async fn login(
+    req: HttpRequest,
     app_state: web::Data<AppState>,
     login_dto: web::Json<LoginDto>,
-) -> Result<impl Responder, actix_web::Error> {
+) -> AppResult<impl Responder> {
     // 获取数据库连接池
     let db = &app_state.db_pool;
+    let ip = client_ip(&req, CFG.login_throttle.trust_forwarded_for);
 
-    // 调用认证服务验证用户登录信息
-    match auth_service::verify_login(db, &login_dto.username, &login_dto.password).await {
-        // 验证成功，获取用户ID
-        Ok(user_id) => {
-            // 生成用户令牌
-            match auth_service::gen_token(db, user_id, &login_dto.username).await {
-                // 令牌生成成功，返回用户令牌
-                Ok(token) => {
-                    info!("User {} logged in successfully", login_dto.username);
-                    Ok(HttpResponse::Ok().json(ResponseResult::<String>::success_with_data(token)))
-                }
-                // 令牌生成失败，记录错误信息并返回错误响应
-                Err(e) => {
-                    error!(
-                        "Failed to generate token for user {}: {}",
-                        login_dto.username, e
-                    );
-                    Ok(HttpResponse::InternalServerError()
-                        .json(ResponseResult::<bool>::fail("token 颁发失败".to_string())))
-                }
-            }
-        }
-        // 验证失败，记录错误信息并返回错误响应
-        Err(e) => {
-            error!(
-                "Failed to verify login for user {}: {}",
-                login_dto.username, e
-            );
-            Ok(HttpResponse::Unauthorized()
-                .json(ResponseResult::<bool>::fail("用户名或密码错误".to_string())))
-        }
-    }
+    // 调用认证服务验证用户登录信息并生成令牌或登录挑战，结果由认证服务记录审计日志
+    let login_vo = auth_service::verify_login(
+        db,
+        &app_state.login_tracker,
+        &login_dto.username,
+        &login_dto.password,
+        ip.as_deref(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<LoginVo>::success_with_data(login_vo)))
+}
+
+/// 两步验证登录的第二步
+///
+/// 使用登录接口返回的挑战令牌与认证器显示的动态验证码换取令牌对，丢失认证器时可使用恢复码
+///
+/// # 参数
+/// - `req`: 请求，用于获取客户端IP
+/// - `app_state`: 应用状态的共享数据
+/// - `totp_dto`: 挑战令牌与动态验证码
+#[post("/login/totp")]
+pub async fn login_totp(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    totp_dto: web::Json<TotpLoginDto>,
+) -> AppResult<impl Responder> {
+    let ip = client_ip(&req, CFG.login_throttle.trust_forwarded_for);
+    let token = auth_service::verify_totp_login(
+        &app_state.db_pool,
+        &app_state.login_tracker,
+        &totp_dto,
+        ip.as_deref(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<TokenPairDto>::success_with_data(token)))
 }
 
 /// 注册新用户
@@ -69,22 +86,234 @@ pub async fn login(
 /// * `register_dto`: 包含用户注册信息的数据传输对象
 ///
 /// # 返回
-/// * `Result<impl Responder>`: 返回一个HTTP响应，包含注册结果或错误信息
+/// * `AppResult<impl Responder>`: 返回一个HTTP响应，包含注册结果或错误信息
 #[post("/register")]
 pub async fn register(
     app_state: web::Data<AppState>,
     register_dto: web::Json<RegisterDto>,
-) -> Result<impl Responder> {
+) -> AppResult<impl Responder> {
     // 调用用户注册服务，传入数据库连接池和用户注册信息
-    match auth_service::user_register(&app_state.db_pool, register_dto.into_inner()).await {
-        // 如果注册成功，返回包含用户信息的HTTP 200响应
-        Ok(user) => {
-            info!("User registered successfully: {:?}", user);
-            Ok(HttpResponse::Ok().json(ResponseResult::<User>::success_with_data(user)))
-        }
-        // 如果注册失败，返回包含错误信息的HTTP 500响应
-        Err(err) => {
-            Ok(HttpResponse::InternalServerError().json(ResponseResult::<String>::fail(err)))
-        }
-    }
+    let user = auth_service::user_register(&app_state.db_pool, register_dto.into_inner()).await?;
+    info!("User registered successfully: {:?}", user);
+    Ok(HttpResponse::Ok().json(ResponseResult::<UserVo>::success_with_data(user)))
+}
+
+/// 使用刷新令牌换取新的访问令牌
+///
+/// 刷新令牌每次使用后都会被轮换，旧的刷新令牌随即失效
+///
+/// # 参数
+/// * `app_state`: 应用程序状态的共享数据，包含数据库连接池等信息
+/// * `refresh_dto`: 包含刷新令牌的数据传输对象
+///
+/// # 返回
+/// * 成功时返回新的访问令牌与刷新令牌，失败时返回401
+#[post("/refresh")]
+pub async fn refresh(
+    app_state: web::Data<AppState>,
+    refresh_dto: web::Json<RefreshTokenDto>,
+) -> AppResult<impl Responder> {
+    let token = auth_service::refresh_token(&app_state.db_pool, &refresh_dto.refresh_token)
+        .await
+        .inspect_err(|err| warn!("Failed to refresh token: {}", err))?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<TokenPairDto>::success_with_data(token)))
+}
+
+/// 退出登录
+///
+/// 作废当前请求携带的访问令牌，请求体中提供刷新令牌时一并作废
+///
+/// # 参数
+/// * `user`: 当前登录用户
+/// * `app_state`: 应用程序状态的共享数据
+/// * `logout_dto`: 可选的请求体，包含刷新令牌
+#[post("/logout")]
+pub async fn logout(
+    user: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    logout_dto: Option<web::Json<LogoutDto>>,
+) -> AppResult<impl Responder> {
+    let refresh_token = logout_dto.and_then(|dto| dto.into_inner().refresh_token);
+
+    auth_service::logout(
+        &app_state.db_pool,
+        &user.claims,
+        refresh_token.as_deref(),
+        &app_state.revocation_store,
+    )
+    .await?;
+    info!("User {} logged out", user.username);
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
+
+/// 退出全部登录
+///
+/// 作废当前用户在所有设备上的访问令牌与刷新令牌
+///
+/// # 参数
+/// * `user`: 当前登录用户
+/// * `app_state`: 应用程序状态的共享数据
+#[post("/logout_all")]
+pub async fn logout_all(
+    user: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+) -> AppResult<impl Responder> {
+    auth_service::logout_all(
+        &app_state.db_pool,
+        &user.claims,
+        &app_state.revocation_store,
+    )
+    .await?;
+    info!("User {} logged out everywhere", user.username);
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
+
+/// 查询当前登录用户的个人信息
+///
+/// # 参数
+/// * `user`: 当前登录用户
+/// * `app_state`: 应用程序状态的共享数据
+#[get("/me")]
+pub async fn me(
+    user: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+) -> AppResult<impl Responder> {
+    let profile = auth_service::get_profile(&app_state.db_pool, &user.claims).await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<ProfileVo>::success_with_data(profile)))
+}
+
+/// 修改当前登录用户的密码
+///
+/// 修改成功后该用户已签发的全部令牌失效，响应中返回新的访问令牌与刷新令牌
+///
+/// # 参数
+/// * `req`: 请求，用于获取客户端IP
+/// * `user`: 当前登录用户
+/// * `app_state`: 应用程序状态的共享数据
+/// * `password_dto`: 原密码与新密码
+#[post("/me/password")]
+pub async fn change_password(
+    req: HttpRequest,
+    user: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    password_dto: web::Json<ChangePasswordDto>,
+) -> AppResult<impl Responder> {
+    let ip = client_ip(&req, CFG.login_throttle.trust_forwarded_for);
+    let token = auth_service::change_password(
+        &app_state.db_pool,
+        &user.claims,
+        password_dto.into_inner(),
+        &app_state.revocation_store,
+        &app_state.login_tracker,
+        ip.as_deref(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<TokenPairDto>::success_with_data(token)))
+}
+
+/// 查询当前用户可见的菜单树
+///
+/// 根据令牌中的角色及其继承的角色返回被授权的页面与按钮资源，按上下级关系组装为树
+///
+/// # 参数
+/// * `user`: 当前登录用户
+/// * `app_state`: 应用程序状态的共享数据
+#[get("/me/menus")]
+pub async fn my_menus(
+    user: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+) -> AppResult<impl Responder> {
+    let menus = resource_service::list_menus(
+        &app_state.db_pool,
+        &app_state.permission_matcher,
+        &user.roles,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<Vec<MenuVo>>::success_with_data(menus)))
+}
+
+/// 查询当前用户的两步验证状态
+#[get("/me/totp")]
+pub async fn totp_status(
+    user: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+) -> AppResult<impl Responder> {
+    let status = totp_service::get_status(&app_state.db_pool, user.id).await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<TotpStatusVo>::success_with_data(status)))
+}
+
+/// 开始绑定认证器
+///
+/// 返回密钥与 otpauth:// URI，用户在认证器中添加后需调用 /auth/me/totp/confirm 确认
+///
+/// # 参数
+/// * `user`: 当前登录用户
+/// * `app_state`: 应用程序状态的共享数据
+#[post("/me/totp/setup")]
+pub async fn setup_totp(
+    user: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+) -> AppResult<impl Responder> {
+    let setup = totp_service::setup_totp(&app_state.db_pool, user.id).await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<TotpSetupVo>::success_with_data(setup)))
+}
+
+/// 提交动态验证码确认绑定，启用两步验证并返回恢复码
+///
+/// # 参数
+/// * `user`: 当前登录用户
+/// * `app_state`: 应用程序状态的共享数据
+/// * `code_dto`: 认证器显示的动态验证码
+#[post("/me/totp/confirm")]
+pub async fn confirm_totp(
+    user: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    code_dto: web::Json<TotpCodeDto>,
+) -> AppResult<impl Responder> {
+    let codes = totp_service::confirm_totp(&app_state.db_pool, user.id, &code_dto.code).await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<RecoveryCodesVo>::success_with_data(codes)))
+}
+
+/// 关闭两步验证，需要登录密码与动态验证码（或恢复码）
+///
+/// # 参数
+/// * `req`: 请求，用于获取客户端IP
+/// * `user`: 当前登录用户
+/// * `app_state`: 应用程序状态的共享数据
+/// * `disable_dto`: 登录密码与动态验证码
+#[post("/me/totp/disable")]
+pub async fn disable_totp(
+    req: HttpRequest,
+    user: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    disable_dto: web::Json<TotpDisableDto>,
+) -> AppResult<impl Responder> {
+    let ip = client_ip(&req, CFG.login_throttle.trust_forwarded_for);
+    totp_service::disable_totp(
+        &app_state.db_pool,
+        user.id,
+        disable_dto.into_inner(),
+        &app_state.login_tracker,
+        ip.as_deref(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
+
+/// 重新生成恢复码，原有恢复码全部作废
+///
+/// # 参数
+/// * `user`: 当前登录用户
+/// * `app_state`: 应用程序状态的共享数据
+/// * `code_dto`: 认证器显示的动态验证码
+#[post("/me/totp/recovery_codes")]
+pub async fn regenerate_recovery_codes(
+    user: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    code_dto: web::Json<TotpCodeDto>,
+) -> AppResult<impl Responder> {
+    let codes =
+        totp_service::regenerate_recovery_codes(&app_state.db_pool, user.id, &code_dto.code)
+            .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<RecoveryCodesVo>::success_with_data(codes)))
 }
@@ -1,82 +1,102 @@
 use crate::{
-    dto::auth_dto::RoleRelPathDto,
-    model::{response::ResponseResult, state::AppState},
-    service::auth_service::load_all_role_rel_resource,
+    dto::auth_dto::{
+        PermissionExplainDto, PermissionExplainQueryDto, PermissionSnapshotDto,
+        PermissionVersionDto,
+    },
+    model::{error::AppResult, response::ResponseResult, state::AppState},
+    service::auth_service::{
+        explain_permission, get_permission_version, reload_permission_matcher,
+    },
 };
-use actix_web::{HttpResponse, Responder, Result, get, web};
-use dashmap::DashMap;
-use std::{collections::HashMap, sync::Arc};
+use actix_web::{HttpResponse, Responder, get, web};
+use std::sync::PoisonError;
 
 /// 异步重新加载所有角色关联资源信息
 ///
-/// 本函数旨在从数据库中重新获取所有角色的关联资源信息，并更新应用状态中的缓存数据
-/// 它首先从应用状态中获取数据库连接池和角色关联资源的共享引用，然后通过调用数据库操作
-/// 加载所有角色的关联资源信息之后，它会清空当前应用状态中的缓存数据，并用新加载的数据进行填充
-/// 最后，它会构建一个包含所有角色关联资源信息的HashMap，并作为HTTP响应返回
+/// 本函数从数据库中重新获取所有角色的关联资源信息并编译为新的权限匹配器，
+/// 编译完成后整体替换应用状态中的匹配器，替换前的请求仍使用旧规则。
+/// 同时递增权限版本号，其他实例轮询到版本变化后也会重新加载。
+/// 数据库读取失败时保留原有规则不变
 ///
 /// # 参数
 /// * `app_state` - 包含数据库连接池和共享状态的Web应用状态
 ///
 /// # 返回值
-/// 返回一个实现了Responder trait的类型，表示HTTP响应，其中包含重新加载的角色关联资源信息
+/// 返回一个实现了Responder trait的类型，表示HTTP响应，其中包含重新加载的角色关联资源信息以及被剔除的无效规则
 #[get("/reflush_role")]
 pub async fn reflush_all_role_rel_resource(
     app_state: web::Data<AppState>,
-) -> Result<impl Responder> {
-    // 获取数据库连接池引用
-    let rb = &app_state.db_pool;
-    // 克隆角色关联资源的共享引用
-    let role_rel_resource_map = Arc::clone(&app_state.role_rel_resource_map);
-    // 异步加载所有角色的关联资源信息
-    let map = load_all_role_rel_resource(rb).await;
-
-    // 清空当前的角色关联资源缓存
-    role_rel_resource_map.clear();
-    // 遍历新加载的角色关联资源信息，并更新缓存
-    for (role, vec) in map {
-        role_rel_resource_map.insert(role, vec);
-    }
-
-    // 构建一个包含所有角色关联资源信息的HashMap，用于返回
-    let response_map = convert_to_hash_map(role_rel_resource_map);
-
+) -> AppResult<impl Responder> {
+    // 重新加载所有角色的关联资源信息，编译后整体替换权限匹配器
+    let snapshot =
+        reload_permission_matcher(&app_state.db_pool, &app_state.permission_matcher).await?;
     // 返回HTTP响应，其中包含重新加载的角色关联资源信息
-    Ok(HttpResponse::Ok().json(
-        ResponseResult::<HashMap<String, Vec<RoleRelPathDto>>>::success_with_data(response_map),
-    ))
+    Ok(
+        HttpResponse::Ok().json(ResponseResult::<PermissionSnapshotDto>::success_with_data(
+            snapshot,
+        )),
+    )
 }
 
 /// 异步获取当前加载的角色关联资源信息
 ///
-/// 该函数通过克隆存储在应用状态中的角色关联资源映射，并将其转换为HashMap格式返回。
-/// 主要用于需要获取角色与资源关系信息的场景，以便进行权限控制或其他操作。
+/// 主要用于需要获取角色与资源关系信息的场景，以便排查权限配置问题。
 ///
 /// # 参数
 ///
-/// * `app_state` - 应用状态的共享引用，其中包含角色关联资源的映射信息
+/// * `app_state` - 应用状态的共享引用，其中包含编译好的权限匹配器
 ///
 /// # 返回
 ///
-/// 返回一个HTTP响应，其中包含角色关联资源的信息。信息以JSON格式呈现，
-/// 并使用`HttpResponse::Ok()`表示请求成功。
+/// 返回一个HTTP响应，其中包含当前生效的角色关联资源信息以及加载时被剔除的无效规则
 #[get("/get_current_role_rel_res")]
 pub async fn get_current_loaded_role_rel_resource(
     app_state: web::Data<AppState>,
-) -> Result<impl Responder> {
-    // 克隆角色关联资源的共享引用
-    let role_rel_resource_map = Arc::clone(&app_state.role_rel_resource_map);
-    // 构建一个包含所有角色关联资源信息的HashMap，用于返回
-    let response_map = convert_to_hash_map(role_rel_resource_map);
-    // 返回HTTP响应，其中包含重新加载的角色关联资源信息
-    Ok(HttpResponse::Ok().json(
-        ResponseResult::<HashMap<String, Vec<RoleRelPathDto>>>::success_with_data(response_map),
-    ))
+) -> AppResult<impl Responder> {
+    let snapshot = app_state
+        .permission_matcher
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .snapshot();
+    Ok(
+        HttpResponse::Ok().json(ResponseResult::<PermissionSnapshotDto>::success_with_data(
+            snapshot,
+        )),
+    )
+}
+
+/// 解释指定用户访问指定接口时的权限判定过程
+///
+/// 返回用户当前的角色、展开继承后参与校验的角色、逐条评估的资源规则、命中的规则以及最终判定，
+/// 用于在不阅读代码的情况下排查403问题
+///
+/// # 参数
+///
+/// * `app_state` - 应用状态的共享引用
+/// * `query` - 用户ID、请求方法（默认 GET）与请求路径
+#[get("/explain")]
+pub async fn explain(
+    app_state: web::Data<AppState>,
+    query: web::Query<PermissionExplainQueryDto>,
+) -> AppResult<impl Responder> {
+    let explain =
+        explain_permission(&app_state.db_pool, &query, &app_state.permission_matcher).await?;
+    Ok(
+        HttpResponse::Ok().json(ResponseResult::<PermissionExplainDto>::success_with_data(
+            explain,
+        )),
+    )
 }
 
-fn convert_to_hash_map(
-    map: Arc<DashMap<String, Vec<RoleRelPathDto>>>,
-) -> HashMap<String, Vec<RoleRelPathDto>> {
-    map.iter()
-        .map(|r| (r.key().clone(), r.value().clone()))
-        .collect()
+/// 查询当前实例已加载的权限版本号与数据库中的最新版本号
+///
+/// 两者一致说明当前实例的权限规则已与数据库同步，可逐个实例调用以确认多实例部署的状态
+#[get("/permission_version")]
+pub async fn permission_version(app_state: web::Data<AppState>) -> AppResult<impl Responder> {
+    let version = get_permission_version(&app_state.db_pool, &app_state.permission_matcher).await?;
+    Ok(
+        HttpResponse::Ok().json(ResponseResult::<PermissionVersionDto>::success_with_data(
+            version,
+        )),
+    )
 }
@@ -1,10 +1,6 @@
-use actix_web::{HttpResponse, Responder};
-use serde_json::json;
+use crate::model::error::{AppError, AppResult};
+use actix_web::HttpResponse;
 // 自定义404处理函数
-pub async fn not_found() -> impl Responder {
-    HttpResponse::NotFound().json(json!({
-        "code": 404,
-        "msg": "Not Found",
-        "data": None::<String>,
-    }))
+pub async fn not_found() -> AppResult<HttpResponse> {
+    Err(AppError::NotFound("Not Found".to_string()))
 }
@@ -0,0 +1,11 @@
+use crate::util::jwt_key_util::get_jwk_set;
+use actix_web::{HttpResponse, Responder, get};
+
+/// 公布JWT验签公钥
+///
+/// 按照RFC 7517的JWK Set格式返回，供其他服务只凭公钥验证本服务签发的令牌。
+/// 使用对称算法时不会公布任何密钥，返回空集合
+#[get("/jwks.json")]
+pub async fn jwks() -> impl Responder {
+    HttpResponse::Ok().json(get_jwk_set())
+}
@@ -1,4 +1,8 @@
 pub mod auth_handler;
 pub mod auth_inner_handler;
 pub mod default_handler;
+pub mod jwks_handler;
+pub mod resource_handler;
+pub mod role_handler;
 pub mod test_handler;
+pub mod user_handler;
@@ -0,0 +1,138 @@
+use crate::config::authenticated_user::AuthenticatedUser;
+use crate::dto::{
+    page_dto::PageVo,
+    resource_dto::{ResourceGrantDto, ResourceQueryDto, ResourceSaveDto, ResourceVo},
+};
+use crate::model::{error::AppResult, response::ResponseResult, state::AppState};
+use crate::service::resource_service;
+use actix_web::{HttpResponse, Responder, delete, get, post, put, web};
+
+/// 分页查询资源
+#[get("")]
+pub async fn page_resources(
+    app_state: web::Data<AppState>,
+    query: web::Query<ResourceQueryDto>,
+) -> AppResult<impl Responder> {
+    let page = resource_service::page_resources(&app_state.db_pool, &query).await?;
+    Ok(
+        HttpResponse::Ok().json(ResponseResult::<PageVo<ResourceVo>>::success_with_data(
+            page,
+        )),
+    )
+}
+
+/// 查询资源详情
+#[get("/{id}")]
+pub async fn get_resource(
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+) -> AppResult<impl Responder> {
+    let resource = resource_service::get_resource(&app_state.db_pool, id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<ResourceVo>::success_with_data(resource)))
+}
+
+/// 创建资源，成功后自动重新加载权限规则
+#[post("")]
+pub async fn create_resource(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    save_dto: web::Json<ResourceSaveDto>,
+) -> AppResult<impl Responder> {
+    let resource = resource_service::create_resource(
+        &app_state.db_pool,
+        &app_state.permission_matcher,
+        operator.id,
+        save_dto.into_inner(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<ResourceVo>::success_with_data(resource)))
+}
+
+/// 修改资源，成功后自动重新加载权限规则
+#[put("/{id}")]
+pub async fn update_resource(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+    save_dto: web::Json<ResourceSaveDto>,
+) -> AppResult<impl Responder> {
+    let resource = resource_service::update_resource(
+        &app_state.db_pool,
+        &app_state.permission_matcher,
+        operator.id,
+        id.into_inner(),
+        save_dto.into_inner(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<ResourceVo>::success_with_data(resource)))
+}
+
+/// 删除资源（软删除），成功后自动重新加载权限规则
+#[delete("/{id}")]
+pub async fn delete_resource(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+) -> AppResult<impl Responder> {
+    resource_service::delete_resource(
+        &app_state.db_pool,
+        &app_state.permission_matcher,
+        operator.id,
+        id.into_inner(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
+
+/// 查询角色被授权的资源
+#[get("/role/{role_id}")]
+pub async fn list_role_resources(
+    app_state: web::Data<AppState>,
+    role_id: web::Path<u64>,
+) -> AppResult<impl Responder> {
+    let resources =
+        resource_service::list_role_resources(&app_state.db_pool, role_id.into_inner()).await?;
+    Ok(
+        HttpResponse::Ok().json(ResponseResult::<Vec<ResourceVo>>::success_with_data(
+            resources,
+        )),
+    )
+}
+
+/// 为角色授权资源，成功后自动重新加载权限规则
+#[post("/role/{role_id}")]
+pub async fn grant_resources(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    role_id: web::Path<u64>,
+    grant_dto: web::Json<ResourceGrantDto>,
+) -> AppResult<impl Responder> {
+    resource_service::grant_resources(
+        &app_state.db_pool,
+        &app_state.permission_matcher,
+        operator.id,
+        role_id.into_inner(),
+        &grant_dto.resource_ids,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
+
+/// 取消角色对资源的授权，成功后自动重新加载权限规则
+#[delete("/role/{role_id}/{resource_id}")]
+pub async fn revoke_resource(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    path: web::Path<(u64, u64)>,
+) -> AppResult<impl Responder> {
+    let (role_id, resource_id) = path.into_inner();
+    resource_service::revoke_resource(
+        &app_state.db_pool,
+        &app_state.permission_matcher,
+        operator.id,
+        role_id,
+        resource_id,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
@@ -0,0 +1,185 @@
+use crate::config::authenticated_user::AuthenticatedUser;
+use crate::dto::{
+    page_dto::PageVo,
+    role_dto::{RoleAssignDto, RoleCreateDto, RoleQueryDto, RoleUpdateDto, RoleVo},
+    user_dto::UserVo,
+};
+use crate::model::{error::AppResult, response::ResponseResult, state::AppState};
+use crate::service::role_service;
+use actix_web::{HttpResponse, Responder, delete, get, post, put, web};
+
+/// 分页查询角色
+#[get("")]
+pub async fn page_roles(
+    app_state: web::Data<AppState>,
+    query: web::Query<RoleQueryDto>,
+) -> AppResult<impl Responder> {
+    let page = role_service::page_roles(&app_state.db_pool, &query).await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<PageVo<RoleVo>>::success_with_data(page)))
+}
+
+/// 查询角色详情
+#[get("/{id}")]
+pub async fn get_role(
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+) -> AppResult<impl Responder> {
+    let role = role_service::get_role(&app_state.db_pool, id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<RoleVo>::success_with_data(role)))
+}
+
+/// 创建角色
+#[post("")]
+pub async fn create_role(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    create_dto: web::Json<RoleCreateDto>,
+) -> AppResult<impl Responder> {
+    let role =
+        role_service::create_role(&app_state.db_pool, operator.id, create_dto.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<RoleVo>::success_with_data(role)))
+}
+
+/// 修改角色名或描述
+#[put("/{id}")]
+pub async fn update_role(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+    update_dto: web::Json<RoleUpdateDto>,
+) -> AppResult<impl Responder> {
+    let role = role_service::update_role(
+        &app_state.db_pool,
+        &app_state.permission_matcher,
+        &app_state.revocation_store,
+        operator.id,
+        id.into_inner(),
+        update_dto.into_inner(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<RoleVo>::success_with_data(role)))
+}
+
+/// 删除角色（软删除），同时取消该角色与用户、资源的全部关联
+#[delete("/{id}")]
+pub async fn delete_role(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+) -> AppResult<impl Responder> {
+    role_service::delete_role(
+        &app_state.db_pool,
+        &app_state.permission_matcher,
+        &app_state.revocation_store,
+        operator.id,
+        id.into_inner(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
+
+/// 查询拥有该角色的用户
+#[get("/{id}/users")]
+pub async fn list_role_users(
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+) -> AppResult<impl Responder> {
+    let users = role_service::list_role_users(&app_state.db_pool, id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<Vec<UserVo>>::success_with_data(users)))
+}
+
+/// 为用户分配角色，这些用户已签发的令牌失效
+#[post("/{id}/users")]
+pub async fn assign_role(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+    assign_dto: web::Json<RoleAssignDto>,
+) -> AppResult<impl Responder> {
+    role_service::assign_role(
+        &app_state.db_pool,
+        &app_state.revocation_store,
+        operator.id,
+        id.into_inner(),
+        &assign_dto.user_ids,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
+
+/// 取消用户的角色，该用户已签发的令牌失效
+#[delete("/{id}/users/{user_id}")]
+pub async fn revoke_role(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    path: web::Path<(u64, u64)>,
+) -> AppResult<impl Responder> {
+    let (id, user_id) = path.into_inner();
+    role_service::revoke_role(
+        &app_state.db_pool,
+        &app_state.revocation_store,
+        operator.id,
+        id,
+        user_id,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
+
+/// 查询角色直接继承的父角色
+#[get("/{id}/parents")]
+pub async fn list_parent_roles(
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+) -> AppResult<impl Responder> {
+    let roles = role_service::list_parent_roles(&app_state.db_pool, id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<Vec<RoleVo>>::success_with_data(roles)))
+}
+
+/// 为角色添加父角色，形成循环继承时拒绝添加
+#[post("/{id}/parents/{parent_id}")]
+pub async fn add_parent_role(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    path: web::Path<(u64, u64)>,
+) -> AppResult<impl Responder> {
+    let (id, parent_id) = path.into_inner();
+    role_service::add_parent_role(
+        &app_state.db_pool,
+        &app_state.permission_matcher,
+        operator.id,
+        id,
+        parent_id,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
+
+/// 取消角色对父角色的继承
+#[delete("/{id}/parents/{parent_id}")]
+pub async fn remove_parent_role(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    path: web::Path<(u64, u64)>,
+) -> AppResult<impl Responder> {
+    let (id, parent_id) = path.into_inner();
+    role_service::remove_parent_role(
+        &app_state.db_pool,
+        &app_state.permission_matcher,
+        operator.id,
+        id,
+        parent_id,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
+
+/// 查询用户拥有的角色
+#[get("/user/{user_id}")]
+pub async fn list_user_roles(
+    app_state: web::Data<AppState>,
+    user_id: web::Path<u64>,
+) -> AppResult<impl Responder> {
+    let roles = role_service::list_user_roles(&app_state.db_pool, user_id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<Vec<RoleVo>>::success_with_data(roles)))
+}
@@ -1,8 +1,8 @@
 use crate::{
-    model::{constance::REQUEST_HEAD_TOKEN, response::ResponseResult, state::AppState},
-    util::token_util::validate_jwt_token,
+    config::authenticated_user::AuthenticatedUser,
+    model::{response::ResponseResult, state::AppState},
 };
-use actix_web::{HttpRequest, HttpResponse, Responder, Result, get, post, web};
+use actix_web::{HttpResponse, Responder, Result, get, post, web};
 
 #[get("/hello")]
 async fn hello() -> impl Responder {
@@ -29,12 +29,6 @@ async fn test_db(app_state: web::Data<AppState>) -> impl Responder {
 }
 
 #[get("/user_id")]
-async fn test_get_user_id(req: HttpRequest) -> Result<impl Responder> {
-    if let Some(token) = req.headers().get(REQUEST_HEAD_TOKEN) {
-        let user_id = validate_jwt_token(token.to_str().unwrap()).unwrap().user_id;
-        Ok(HttpResponse::Ok().json(ResponseResult::<u64>::success_with_data(user_id)))
-    } else {
-        Ok(HttpResponse::InternalServerError()
-            .json(ResponseResult::<String>::fail("获取token失败".to_string())))
-    }
+async fn test_get_user_id(user: AuthenticatedUser) -> Result<impl Responder> {
+    Ok(HttpResponse::Ok().json(ResponseResult::<u64>::success_with_data(user.id)))
 }
@@ -0,0 +1,139 @@
+use crate::config::authenticated_user::AuthenticatedUser;
+use crate::dto::{
+    page_dto::PageVo,
+    user_dto::{ResetPasswordDto, UserQueryDto, UserUpdateDto, UserVo},
+};
+use crate::model::{error::AppResult, response::ResponseResult, state::AppState};
+use crate::service::{totp_service, user_service};
+use actix_web::{HttpResponse, Responder, delete, get, post, put, web};
+
+/// 分页查询用户
+///
+/// # 参数
+/// * `app_state`: 应用程序状态的共享数据
+/// * `query`: 分页与过滤条件
+#[get("")]
+pub async fn page_users(
+    app_state: web::Data<AppState>,
+    query: web::Query<UserQueryDto>,
+) -> AppResult<impl Responder> {
+    let page = user_service::page_users(&app_state.db_pool, &query).await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<PageVo<UserVo>>::success_with_data(page)))
+}
+
+/// 查询用户详情
+#[get("/{id}")]
+pub async fn get_user(
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+) -> AppResult<impl Responder> {
+    let user = user_service::get_user(&app_state.db_pool, id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<UserVo>::success_with_data(user)))
+}
+
+/// 修改用户基本信息
+///
+/// # 参数
+/// * `operator`: 当前登录用户
+/// * `app_state`: 应用程序状态的共享数据
+/// * `id`: 被修改的用户ID
+/// * `update_dto`: 修改内容
+#[put("/{id}")]
+pub async fn update_user(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+    update_dto: web::Json<UserUpdateDto>,
+) -> AppResult<impl Responder> {
+    let user = user_service::update_user(
+        &app_state.db_pool,
+        operator.id,
+        id.into_inner(),
+        update_dto.into_inner(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<UserVo>::success_with_data(user)))
+}
+
+/// 启用用户
+#[post("/{id}/enable")]
+pub async fn enable_user(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+) -> AppResult<impl Responder> {
+    user_service::set_user_status(
+        &app_state.db_pool,
+        &app_state.revocation_store,
+        operator.id,
+        id.into_inner(),
+        true,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
+
+/// 禁用用户，该用户已签发的令牌全部失效
+#[post("/{id}/disable")]
+pub async fn disable_user(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+) -> AppResult<impl Responder> {
+    user_service::set_user_status(
+        &app_state.db_pool,
+        &app_state.revocation_store,
+        operator.id,
+        id.into_inner(),
+        false,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
+
+/// 删除用户（软删除），该用户已签发的令牌全部失效
+#[delete("/{id}")]
+pub async fn delete_user(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+) -> AppResult<impl Responder> {
+    user_service::delete_user(
+        &app_state.db_pool,
+        &app_state.revocation_store,
+        operator.id,
+        id.into_inner(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
+
+/// 重置用户密码，该用户已签发的令牌全部失效
+#[post("/{id}/reset_password")]
+pub async fn reset_password(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+    reset_dto: web::Json<ResetPasswordDto>,
+) -> AppResult<impl Responder> {
+    user_service::reset_password(
+        &app_state.db_pool,
+        &app_state.revocation_store,
+        operator.id,
+        id.into_inner(),
+        reset_dto.into_inner(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
+
+/// 重置用户的两步验证，用于用户丢失认证器且没有可用的恢复码
+#[post("/{id}/reset_totp")]
+pub async fn reset_totp(
+    operator: AuthenticatedUser,
+    app_state: web::Data<AppState>,
+    id: web::Path<u64>,
+) -> AppResult<impl Responder> {
+    totp_service::reset_totp(&app_state.db_pool, operator.id, id.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(ResponseResult::<bool>::success_without_data()))
+}
@@ -9,12 +9,29 @@ mod util;
 use actix_web::{App, HttpServer, middleware::Logger, web};
 use config::logger::init_logger;
 use handler::default_handler::not_found;
-use log::{debug, warn};
-use model::{config::Setting, state::AppState};
-use router::{auth_inner_router, auth_router, test_router};
-use service::auth_service::load_all_role_rel_resource;
-use std::sync::Arc;
-use util::{config_util::CFG, db_util};
+use log::{debug, error, warn};
+use model::{
+    config::Setting,
+    constance::{LOGIN_ATTEMPT_PURGE_INTERVAL_SECOND, REVOCATION_PURGE_INTERVAL_SECOND},
+    error::AppError,
+    state::AppState,
+};
+use router::{
+    auth_inner_router, auth_router, resource_router, role_router, test_router, user_router,
+    well_known_router,
+};
+use service::{
+    auth_service::{DUMMY_PASSWORD_HASH, load_all_role_rel_resource, sync_permission_matcher},
+    login_throttle_service::LoginAttemptTracker,
+    permission_service::{ACCESS_RULES, PermissionMatcher},
+    role_service,
+    token_revocation_service::TokenRevocationStore,
+};
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use util::{config_util::CFG, db_util, jwt_key_util::JWT_KEY_RING, password_util::PASSWORD_HASHER};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -26,15 +43,68 @@ async fn main() -> std::io::Result<()> {
         Ok(pool) => pool,
         Err(e) => {
             eprintln!("Failed to get database instance: {}", e);
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Database connection failed",
-            ));
+            return Err(std::io::Error::other("Database connection failed"));
+        }
+    };
+
+    // 加载角色资源并编译权限匹配器，加载失败时所有受保护的接口都将拒绝访问
+    let permission_matcher = match load_all_role_rel_resource(&db_pool).await {
+        Ok(matcher) => matcher,
+        Err(err) => {
+            error!("角色资源关联数据加载失败: {}", err);
+            PermissionMatcher::default()
         }
     };
+    let permission_matcher = Arc::new(RwLock::new(permission_matcher));
+    if let Err(err) = role_service::check_super_role_holders(&db_pool).await {
+        error!("超级管理员角色检查失败: {}", err);
+    }
 
-    // 加载角色资源映射，并处理可能的错误
-    let role_rel_resource_map = Arc::new(load_all_role_rel_resource(&db_pool).await);
+    // 定时轮询权限版本号，其他实例修改权限数据后本实例也会重新加载
+    if CFG.auth.permission_poll_interval_second > 0 {
+        let db_pool = db_pool.clone();
+        let permission_matcher = Arc::clone(&permission_matcher);
+        actix_rt::spawn(async move {
+            let mut interval = actix_rt::time::interval(Duration::from_secs(
+                CFG.auth.permission_poll_interval_second,
+            ));
+            loop {
+                interval.tick().await;
+                if let Err(err) = sync_permission_matcher(&db_pool, &permission_matcher).await {
+                    warn!("权限版本号检查失败: {}", err);
+                }
+            }
+        });
+    }
+
+    // 加载令牌作废记录，并定时清理已过期的记录
+    let revocation_store = Arc::new(TokenRevocationStore::load(&db_pool).await);
+    {
+        let db_pool = db_pool.clone();
+        let revocation_store = Arc::clone(&revocation_store);
+        actix_rt::spawn(async move {
+            let mut interval =
+                actix_rt::time::interval(Duration::from_secs(REVOCATION_PURGE_INTERVAL_SECOND));
+            loop {
+                interval.tick().await;
+                revocation_store.purge_expired(&db_pool).await;
+            }
+        });
+    }
+
+    // 登录失败计数器，定时清理过期的失败记录
+    let login_tracker = Arc::new(LoginAttemptTracker::new(CFG.login_throttle.clone()));
+    {
+        let login_tracker = Arc::clone(&login_tracker);
+        actix_rt::spawn(async move {
+            let mut interval =
+                actix_rt::time::interval(Duration::from_secs(LOGIN_ATTEMPT_PURGE_INTERVAL_SECOND));
+            loop {
+                interval.tick().await;
+                login_tracker.purge_expired();
+            }
+        });
+    }
 
     // 获取配置文件中的设置
     let setting = match &CFG.setting {
@@ -54,6 +124,15 @@ async fn main() -> std::io::Result<()> {
         ));
     }
 
+    // 提前加载JWT签名密钥，配置有误时在启动阶段即报错
+    lazy_static::initialize(&JWT_KEY_RING);
+    // 提前编译认证路径规则，规则无效时在启动阶段即报错
+    lazy_static::initialize(&ACCESS_RULES);
+    // 提前构建密码哈希算法，参数无效时在启动阶段即报错
+    lazy_static::initialize(&PASSWORD_HASHER);
+    // 提前生成占位密码哈希，避免首次登录失败时的额外耗时
+    lazy_static::initialize(&DUMMY_PASSWORD_HASH);
+
     // 打印实际使用的地址和端口
     debug!("Starting server on {}:{}", setting.host, setting.port);
 
@@ -63,11 +142,27 @@ async fn main() -> std::io::Result<()> {
             .wrap(Logger::default())
             .app_data(web::Data::new(AppState {
                 db_pool: db_pool.clone(),
-                role_rel_resource_map: role_rel_resource_map.clone(),
+                permission_matcher: permission_matcher.clone(),
+                revocation_store: revocation_store.clone(),
+                login_tracker: login_tracker.clone(),
+            }))
+            // 请求体与查询参数解析失败时，同样返回统一的错误格式
+            .app_data(web::JsonConfig::default().error_handler(|err, _| {
+                AppError::BadRequest(format!("请求参数错误: {}", err)).into()
+            }))
+            .app_data(web::QueryConfig::default().error_handler(|err, _| {
+                AppError::BadRequest(format!("请求参数错误: {}", err)).into()
+            }))
+            .app_data(web::PathConfig::default().error_handler(|err, _| {
+                AppError::BadRequest(format!("请求参数错误: {}", err)).into()
             }))
             .configure(auth_router::routes)
             .configure(test_router::routes)
             .configure(auth_inner_router::routes)
+            .configure(user_router::routes)
+            .configure(role_router::routes)
+            .configure(resource_router::routes)
+            .configure(well_known_router::routes)
             // 设置默认服务处理未匹配的路由
             .default_service(web::route().to(not_found))
     })
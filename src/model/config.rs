@@ -5,6 +5,16 @@ pub struct Config {
     pub setting: Option<Setting>,
     pub database: Database,
     pub jwt: Jwt,
+    #[serde(default)]
+    pub auth: Auth,
+    #[serde(default)]
+    pub login_throttle: LoginThrottle,
+    #[serde(default)]
+    pub totp: Totp,
+    #[serde(default)]
+    pub password_policy: PasswordPolicy,
+    #[serde(default)]
+    pub password_hash: PasswordHashing,
 }
 
 //--------------------------------------
@@ -49,11 +59,353 @@ impl Default for Setting {
 
 #[derive(Deserialize, Debug, Default, Clone)]
 pub struct Jwt {
+    // 对称算法（HS*）使用的密钥
+    #[serde(default)]
+    pub token_secret: String,
+    // 签名算法，支持 HS256/HS384/HS512/RS256/RS384/RS512/PS256/PS384/PS512/ES256/ES384/EdDSA
+    #[serde(default = "default_jwt_algorithm")]
+    pub algorithm: String,
+    // 非对称算法使用的PEM私钥路径
+    pub private_key_path: Option<String>,
+    // 非对称算法使用的PEM公钥路径
+    pub public_key_path: Option<String>,
+    // 用于签发新令牌的密钥ID，未配置密钥环时为 default
+    pub active_kid: Option<String>,
+    // 密钥环，配置后忽略上面的单密钥配置
+    #[serde(default)]
+    pub keys: Vec<JwtKeyConfig>,
+    // 访问令牌有效期，单位分钟
+    #[serde(default = "default_jwt_access_expiration_time")]
+    pub access_expiration_time: i64,
+    // 已废弃的旧配置项，单位为小时；配置后加载失败，提示改为access_expiration_time
+    #[serde(default)]
+    pub expiration_time: Option<i64>,
+    // 刷新令牌有效期，单位小时
+    #[serde(default = "default_jwt_refresh_expiration_time")]
+    pub refresh_expiration_time: i64,
+}
+
+// 密钥环中的一个密钥，除当前签发密钥外其余密钥只用于验签
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct JwtKeyConfig {
+    pub kid: String,
+    #[serde(default = "default_jwt_algorithm")]
+    pub algorithm: String,
+    #[serde(default)]
     pub token_secret: String,
-    #[serde(default = "default_jwt_expiration_time")]
-    pub expiration_time: i64,
+    // 仅用于验签的非对称密钥可以不配置私钥
+    pub private_key_path: Option<String>,
+    pub public_key_path: Option<String>,
+}
+
+fn default_jwt_algorithm() -> String {
+    super::constance::DEFAULT_JWT_ALGORITHM.to_string()
+}
+
+fn default_jwt_access_expiration_time() -> i64 {
+    super::constance::DEFAULT_JWT_ACCESS_EXPIRATION_TIME_MINUTE
+}
+
+fn default_jwt_refresh_expiration_time() -> i64 {
+    super::constance::DEFAULT_JWT_REFRESH_EXPIRATION_TIME_HOUR
+}
+
+//--------------------------------------
+
+// 令牌来源
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenSource {
+    // Authorization: Bearer <token>
+    Bearer,
+    // 自定义请求头 token
+    Header,
+    // Cookie
+    Cookie,
+    // 查询参数
+    Query,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Auth {
+    // 按顺序尝试的令牌来源
+    #[serde(default = "default_auth_token_sources")]
+    pub token_sources: Vec<TokenSource>,
+    #[serde(default = "default_auth_token_cookie_name")]
+    pub token_cookie_name: String,
+    #[serde(default = "default_auth_token_query_param")]
+    pub token_query_param: String,
+    // 无需令牌即可访问的路径，在内置规则之外追加
+    #[serde(default)]
+    pub public_paths: Vec<PathRule>,
+    // 只需有效令牌、不校验资源权限的路径，在内置规则之外追加
+    #[serde(default)]
+    pub authenticated_paths: Vec<PathRule>,
+    // 超级管理员角色名，拥有该角色的用户跳过资源权限校验，为空表示不启用
+    #[serde(default)]
+    pub super_role: Option<String>,
+    // 轮询权限规则版本号的间隔，版本号变化时重新加载权限规则，为0时不轮询
+    #[serde(default = "default_auth_permission_poll_interval_second")]
+    pub permission_poll_interval_second: u64,
+}
+
+fn default_auth_permission_poll_interval_second() -> u64 {
+    super::constance::DEFAULT_PERMISSION_POLL_INTERVAL_SECOND
+}
+
+/// 路径规则
+///
+/// `path`以`^`开头时按正则表达式匹配，否则按通配符匹配：
+/// `*`匹配单级路径中的任意字符，`**`匹配任意多级路径
+#[derive(Deserialize, Debug, Clone)]
+pub struct PathRule {
+    pub path: String,
+    // 限定的请求方法，为空表示全部方法
+    #[serde(default)]
+    pub methods: Vec<String>,
+}
+
+fn default_auth_token_sources() -> Vec<TokenSource> {
+    vec![TokenSource::Bearer, TokenSource::Header]
+}
+
+fn default_auth_token_cookie_name() -> String {
+    super::constance::REQUEST_HEAD_TOKEN.to_string()
+}
+
+fn default_auth_token_query_param() -> String {
+    super::constance::REQUEST_HEAD_TOKEN.to_string()
+}
+
+impl Default for Auth {
+    fn default() -> Self {
+        Self {
+            token_sources: default_auth_token_sources(),
+            token_cookie_name: default_auth_token_cookie_name(),
+            token_query_param: default_auth_token_query_param(),
+            public_paths: Vec::new(),
+            authenticated_paths: Vec::new(),
+            super_role: None,
+            permission_poll_interval_second: default_auth_permission_poll_interval_second(),
+        }
+    }
+}
+
+//--------------------------------------
+
+// 登录失败限制
+#[derive(Deserialize, Debug, Clone)]
+pub struct LoginThrottle {
+    #[serde(default = "default_login_throttle_enabled")]
+    pub enabled: bool,
+    // 同一用户名连续失败多少次后锁定
+    #[serde(default = "default_login_throttle_max_failures_per_user")]
+    pub max_failures_per_user: u32,
+    // 同一客户端IP连续失败多少次后锁定
+    #[serde(default = "default_login_throttle_max_failures_per_ip")]
+    pub max_failures_per_ip: u32,
+    // 距上次失败超过该时长后失败次数清零，距上次锁定结束超过该时长后锁定时长恢复初始值
+    #[serde(default = "default_login_throttle_failure_window_second")]
+    pub failure_window_second: i64,
+    // 首次锁定的时长，之后每次锁定翻倍
+    #[serde(default = "default_login_throttle_base_lock_second")]
+    pub base_lock_second: i64,
+    // 锁定时长上限
+    #[serde(default = "default_login_throttle_max_lock_second")]
+    pub max_lock_second: i64,
+    // 是否信任X-Forwarded-For等代理请求头中的客户端IP，仅在服务部署于可信代理之后时开启
+    #[serde(default)]
+    pub trust_forwarded_for: bool,
+}
+
+fn default_login_throttle_enabled() -> bool {
+    true
+}
+
+fn default_login_throttle_max_failures_per_user() -> u32 {
+    super::constance::DEFAULT_LOGIN_MAX_FAILURES_PER_USER
+}
+
+fn default_login_throttle_max_failures_per_ip() -> u32 {
+    super::constance::DEFAULT_LOGIN_MAX_FAILURES_PER_IP
+}
+
+fn default_login_throttle_failure_window_second() -> i64 {
+    super::constance::DEFAULT_LOGIN_FAILURE_WINDOW_SECOND
+}
+
+fn default_login_throttle_base_lock_second() -> i64 {
+    super::constance::DEFAULT_LOGIN_BASE_LOCK_SECOND
 }
 
-fn default_jwt_expiration_time() -> i64 {
-    super::constance::DEFAULT_JWT_EXPIRATION_TIME_HOUR
+fn default_login_throttle_max_lock_second() -> i64 {
+    super::constance::DEFAULT_LOGIN_MAX_LOCK_SECOND
+}
+
+impl Default for LoginThrottle {
+    fn default() -> Self {
+        LoginThrottle {
+            enabled: default_login_throttle_enabled(),
+            max_failures_per_user: default_login_throttle_max_failures_per_user(),
+            max_failures_per_ip: default_login_throttle_max_failures_per_ip(),
+            failure_window_second: default_login_throttle_failure_window_second(),
+            base_lock_second: default_login_throttle_base_lock_second(),
+            max_lock_second: default_login_throttle_max_lock_second(),
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+//--------------------------------------
+
+// 两步验证
+#[derive(Deserialize, Debug, Clone)]
+pub struct Totp {
+    // 认证器应用中显示的签发方名称
+    #[serde(default = "default_totp_issuer")]
+    pub issuer: String,
+    // 密码校验通过后，提交动态验证码的有效期
+    #[serde(default = "default_totp_challenge_expiration_second")]
+    pub challenge_expiration_second: i64,
+    // 允许前后各偏差多少个时间步（30秒）
+    #[serde(default = "default_totp_allowed_skew_step")]
+    pub allowed_skew_step: u64,
+    // 每次生成的恢复码数量
+    #[serde(default = "default_totp_recovery_code_count")]
+    pub recovery_code_count: usize,
+}
+
+fn default_totp_issuer() -> String {
+    super::constance::DEFAULT_TOTP_ISSUER.to_string()
+}
+
+fn default_totp_challenge_expiration_second() -> i64 {
+    super::constance::DEFAULT_TOTP_CHALLENGE_EXPIRATION_SECOND
+}
+
+fn default_totp_allowed_skew_step() -> u64 {
+    super::constance::DEFAULT_TOTP_ALLOWED_SKEW_STEP
+}
+
+fn default_totp_recovery_code_count() -> usize {
+    super::constance::DEFAULT_TOTP_RECOVERY_CODE_COUNT
+}
+
+impl Default for Totp {
+    fn default() -> Self {
+        Totp {
+            issuer: default_totp_issuer(),
+            challenge_expiration_second: default_totp_challenge_expiration_second(),
+            allowed_skew_step: default_totp_allowed_skew_step(),
+            recovery_code_count: default_totp_recovery_code_count(),
+        }
+    }
+}
+
+//--------------------------------------
+
+// 密码策略，注册、修改密码与重置密码时校验
+#[derive(Deserialize, Debug, Clone)]
+pub struct PasswordPolicy {
+    // 最小长度，按字符计
+    #[serde(default = "default_password_min_length")]
+    pub min_length: usize,
+    // 最大长度，按字符计
+    #[serde(default = "default_password_max_length")]
+    pub max_length: usize,
+    // 至少包含小写字母、大写字母、数字、符号中的几类
+    #[serde(default = "default_password_min_character_classes")]
+    pub min_character_classes: usize,
+    // 是否拒绝内置常见密码列表中的密码
+    #[serde(default = "default_true")]
+    pub deny_common_passwords: bool,
+    // 是否拒绝包含用户名的密码
+    #[serde(default = "default_true")]
+    pub deny_username: bool,
+}
+
+fn default_password_min_length() -> usize {
+    super::constance::DEFAULT_PASSWORD_MIN_LENGTH
+}
+
+fn default_password_max_length() -> usize {
+    super::constance::DEFAULT_PASSWORD_MAX_LENGTH
+}
+
+fn default_password_min_character_classes() -> usize {
+    super::constance::DEFAULT_PASSWORD_MIN_CHARACTER_CLASSES
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy {
+            min_length: default_password_min_length(),
+            max_length: default_password_max_length(),
+            min_character_classes: default_password_min_character_classes(),
+            deny_common_passwords: true,
+            deny_username: true,
+        }
+    }
+}
+
+//--------------------------------------
+
+// 密码哈希算法
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PasswordHashAlgorithm {
+    #[default]
+    Bcrypt,
+    Argon2id,
+}
+
+// 密码哈希配置，修改后已有的密码哈希会在用户下次登录成功时按新配置重新计算
+#[derive(Deserialize, Debug, Clone)]
+pub struct PasswordHashing {
+    // 新密码使用的算法，校验时按哈希本身的格式识别算法
+    #[serde(default)]
+    pub algorithm: PasswordHashAlgorithm,
+    #[serde(default = "default_bcrypt_cost")]
+    pub bcrypt_cost: u32,
+    // Argon2id 的内存开销，单位KiB
+    #[serde(default = "default_argon2_memory_kib")]
+    pub argon2_memory_kib: u32,
+    // Argon2id 的迭代次数
+    #[serde(default = "default_argon2_iterations")]
+    pub argon2_iterations: u32,
+    // Argon2id 的并行度
+    #[serde(default = "default_argon2_parallelism")]
+    pub argon2_parallelism: u32,
+}
+
+fn default_bcrypt_cost() -> u32 {
+    super::constance::DEFAULT_BCRYPT_COST
+}
+
+fn default_argon2_memory_kib() -> u32 {
+    super::constance::DEFAULT_ARGON2_MEMORY_KIB
+}
+
+fn default_argon2_iterations() -> u32 {
+    super::constance::DEFAULT_ARGON2_ITERATIONS
+}
+
+fn default_argon2_parallelism() -> u32 {
+    super::constance::DEFAULT_ARGON2_PARALLELISM
+}
+
+impl Default for PasswordHashing {
+    fn default() -> Self {
+        PasswordHashing {
+            algorithm: PasswordHashAlgorithm::default(),
+            bcrypt_cost: default_bcrypt_cost(),
+            argon2_memory_kib: default_argon2_memory_kib(),
+            argon2_iterations: default_argon2_iterations(),
+            argon2_parallelism: default_argon2_parallelism(),
+        }
+    }
 }
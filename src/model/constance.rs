@@ -1,10 +1,68 @@
-pub const DEFAULT_HOST: &'static str = "0.0.0.0";
+pub const DEFAULT_HOST: &str = "0.0.0.0";
 pub const DEFAULT_PORT: u16 = 8080;
-pub const DEFAULT_JWT_EXPIRATION_TIME_HOUR: i64 = 6;
+pub const DEFAULT_JWT_ALGORITHM: &str = "HS512";
+pub const DEFAULT_JWT_KID: &str = "default";
+pub const DEFAULT_JWT_ACCESS_EXPIRATION_TIME_MINUTE: i64 = 30;
+pub const DEFAULT_JWT_REFRESH_EXPIRATION_TIME_HOUR: i64 = 7 * 24;
 pub const DEFAULT_BCRYPT_COST: u32 = 12;
+// Argon2id 的默认参数，取自 OWASP 推荐的 m=19MiB, t=2, p=1
+pub const DEFAULT_ARGON2_MEMORY_KIB: u32 = 19 * 1024;
+pub const DEFAULT_ARGON2_ITERATIONS: u32 = 2;
+pub const DEFAULT_ARGON2_PARALLELISM: u32 = 1;
 
-pub const REQUEST_HEAD_TOKEN: &'static str = "token";
+pub const REQUEST_HEAD_TOKEN: &str = "token";
+pub const BEARER_PREFIX: &str = "Bearer ";
 
+#[allow(dead_code)]
 pub const DELETED: u8 = 1;
 
-pub const NOT_DELETED: u8 = 0;
\ No newline at end of file
+pub const NOT_DELETED: u8 = 0;
+
+pub const USER_STATUS_DISABLED: u8 = 0;
+pub const USER_STATUS_ENABLED: u8 = 1;
+
+pub const TOTP_DISABLED: u8 = 0;
+pub const TOTP_ENABLED: u8 = 1;
+
+pub const RESOURCE_TYPE_PATH: u8 = 1;
+pub const RESOURCE_TYPE_PAGE: u8 = 2;
+pub const RESOURCE_TYPE_BUTTON: u8 = 3;
+
+pub const DEFAULT_PAGE_SIZE: u64 = 10;
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+pub const REVOKE_TYPE_TOKEN: u8 = 1;
+pub const REVOKE_TYPE_USER: u8 = 2;
+// 作废记录的清理间隔
+pub const REVOCATION_PURGE_INTERVAL_SECOND: u64 = 10 * 60;
+// 未作废结论在内存中的缓存时长，过期后会再次回查数据库
+pub const REVOCATION_CHECK_CACHE_SECOND: i64 = 30;
+// 用户权限版本号在内存中的缓存时长，过期后会再次回查数据库
+pub const AUTH_VERSION_CACHE_SECOND: i64 = 30;
+// 轮询权限规则版本号的默认间隔，为0时不轮询
+pub const DEFAULT_PERMISSION_POLL_INTERVAL_SECOND: u64 = 10;
+
+// 审计日志使用的日志target
+pub const AUDIT_LOG_TARGET: &str = "audit";
+
+// 登录失败限制的默认值
+pub const DEFAULT_LOGIN_MAX_FAILURES_PER_USER: u32 = 5;
+pub const DEFAULT_LOGIN_MAX_FAILURES_PER_IP: u32 = 20;
+pub const DEFAULT_LOGIN_FAILURE_WINDOW_SECOND: i64 = 15 * 60;
+pub const DEFAULT_LOGIN_BASE_LOCK_SECOND: i64 = 60;
+pub const DEFAULT_LOGIN_MAX_LOCK_SECOND: i64 = 60 * 60;
+// 登录失败记录的清理间隔
+pub const LOGIN_ATTEMPT_PURGE_INTERVAL_SECOND: u64 = 10 * 60;
+
+// 两步验证的默认值
+pub const DEFAULT_TOTP_ISSUER: &str = "papudding-backend-template";
+pub const DEFAULT_TOTP_CHALLENGE_EXPIRATION_SECOND: i64 = 5 * 60;
+pub const DEFAULT_TOTP_ALLOWED_SKEW_STEP: u64 = 1;
+pub const DEFAULT_TOTP_RECOVERY_CODE_COUNT: usize = 10;
+// 登录挑战令牌的用途标识
+pub const TOTP_CHALLENGE_PURPOSE: &str = "totp";
+
+// 密码策略的默认值，bcrypt只使用密码的前72个字节
+pub const DEFAULT_PASSWORD_MIN_LENGTH: usize = 8;
+pub const DEFAULT_PASSWORD_MAX_LENGTH: usize = 72;
+pub const DEFAULT_PASSWORD_MIN_CHARACTER_CLASSES: usize = 2;
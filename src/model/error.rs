@@ -0,0 +1,185 @@
+use crate::model::response::ResponseResult;
+use actix_web::{
+    HttpResponse, ResponseError,
+    http::{StatusCode, header::RETRY_AFTER},
+};
+use log::error;
+use serde::Serialize;
+use thiserror::Error;
+
+/// 应用统一错误类型
+///
+/// 每种错误对应一个稳定的业务码与HTTP状态码，响应体统一为`ResponseResult`格式。
+/// 服务器内部错误只返回通用提示，具体原因记录在日志中
+#[derive(Error, Debug)]
+pub enum AppError {
+    // 请求参数错误
+    #[error("{0}")]
+    BadRequest(String),
+    // 请求参数校验失败，响应的data中包含每个字段的错误
+    #[error("参数校验失败")]
+    Validation(Vec<FieldError>),
+    // 未登录或令牌缺失
+    #[error("{0}")]
+    Unauthorized(String),
+    // 用户名或密码错误
+    #[error("用户名或密码错误")]
+    InvalidCredentials,
+    // 访问令牌无效、过期或已作废
+    #[error("{0}")]
+    InvalidToken(String),
+    // 动态验证码或恢复码错误
+    #[error("动态验证码错误")]
+    InvalidOtp,
+    // 刷新令牌无效、过期或已作废
+    #[error("{0}")]
+    InvalidRefreshToken(String),
+    // 没有访问权限
+    #[error("{0}")]
+    Forbidden(String),
+    // 账号已被禁用
+    #[error("账号已禁用")]
+    AccountDisabled,
+    // 登录失败次数过多被临时锁定，参数为剩余锁定秒数
+    #[error("登录失败次数过多，请{0}秒后重试")]
+    AccountLocked(i64),
+    // 资源不存在
+    #[error("{0}")]
+    NotFound(String),
+    // 资源冲突，如用户名已存在
+    #[error("{0}")]
+    Conflict(String),
+    // 数据库错误
+    #[error("数据库操作失败: {0}")]
+    Database(#[from] rbatis::Error),
+    // 其他内部错误
+    #[error("{0}")]
+    Internal(String),
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+/// 字段校验错误
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FieldError {
+    // 请求体中的字段名
+    pub field: String,
+    // 稳定的错误标识，客户端可据此展示本地化的提示
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: String) -> Self {
+        FieldError {
+            field: field.to_string(),
+            code: code.to_string(),
+            message,
+        }
+    }
+}
+
+impl AppError {
+    /// 业务码，客户端应据此而不是提示信息判断错误类型
+    pub fn code(&self) -> i32 {
+        match self {
+            AppError::BadRequest(_) => 40000,
+            AppError::Validation(_) => 40001,
+            AppError::Unauthorized(_) => 40100,
+            AppError::InvalidCredentials => 40101,
+            AppError::InvalidToken(_) => 40102,
+            AppError::InvalidRefreshToken(_) => 40103,
+            AppError::InvalidOtp => 40104,
+            AppError::Forbidden(_) => 40300,
+            AppError::AccountDisabled => 40301,
+            AppError::NotFound(_) => 40400,
+            AppError::AccountLocked(_) => 42900,
+            AppError::Conflict(_) => 40900,
+            AppError::Internal(_) => 50000,
+            AppError::Database(_) => 50001,
+        }
+    }
+
+    // 返回给客户端的提示信息
+    fn client_msg(&self) -> String {
+        match self {
+            AppError::Database(_) | AppError::Internal(_) => "服务器内部错误".to_string(),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) | AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_)
+            | AppError::InvalidCredentials
+            | AppError::InvalidToken(_)
+            | AppError::InvalidRefreshToken(_)
+            | AppError::InvalidOtp => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) | AppError::AccountDisabled => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::AccountLocked(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if self.status_code().is_server_error() {
+            error!("{}", self);
+        }
+        let mut builder = HttpResponse::build(self.status_code());
+        if let AppError::AccountLocked(retry_after) = self {
+            builder.insert_header((RETRY_AFTER, retry_after.to_string()));
+        }
+        match self {
+            AppError::Validation(errors) => builder.json(ResponseResult::fail_with_data(
+                self.code(),
+                self.client_msg(),
+                errors,
+            )),
+            _ => builder.json(ResponseResult::<bool>::fail_with_code(
+                self.code(),
+                self.client_msg(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::body::to_bytes;
+
+    #[actix_rt::test]
+    async fn test_error_response() {
+        let resp = AppError::Forbidden("权限验证失败".to_string()).error_response();
+        assert_eq!(StatusCode::FORBIDDEN, resp.status());
+        let body = to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(40300, json["code"]);
+        assert_eq!("权限验证失败", json["msg"]);
+
+        // 内部错误不向客户端暴露细节
+        let resp = AppError::Internal("secret detail".to_string()).error_response();
+        let body = to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!("服务器内部错误", json["msg"]);
+
+        // 字段校验错误在data中返回
+        let resp = AppError::Validation(vec![FieldError::new(
+            "password",
+            "too_short",
+            "密码长度不能少于8位".to_string(),
+        )])
+        .error_response();
+        assert_eq!(StatusCode::BAD_REQUEST, resp.status());
+        let body = to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(40001, json["code"]);
+        assert_eq!("password", json["data"][0]["field"]);
+        assert_eq!("too_short", json["data"][0]["code"]);
+    }
+}
@@ -1,5 +1,8 @@
 pub mod config;
 pub mod constance;
+pub mod error;
+pub mod permission;
 pub mod response;
 pub mod state;
+pub mod token;
 pub mod user;
@@ -0,0 +1,21 @@
+use rbatis::{self, RBatis, impl_select, rbdc::DateTime, rbdc::db::ExecResult, sql};
+use serde::{Deserialize, Serialize};
+
+// 权限规则版本号，全表只有 id = 1 一行，各实例轮询该版本号判断是否需要重新加载权限规则
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PermissionVersion {
+    pub id: Option<u8>,
+    pub version: Option<u64>,
+    pub update_time: Option<DateTime>,
+}
+
+rbatis::crud!(PermissionVersion {}, "sys_permission_version");
+impl_select!(PermissionVersion{select_current() -> Option => "`where id = 1 limit 1`"}, "sys_permission_version");
+
+// 版本号加一，记录不存在时创建
+#[sql(
+    "insert into sys_permission_version (id, version, update_time)
+    values (1, 1, now())
+    on duplicate key update version = version + 1, update_time = now()"
+)]
+pub async fn bump_permission_version(rb: &RBatis) -> ExecResult {}
@@ -32,10 +32,22 @@ impl<T> ResponseResult<T> {
     }
 
     pub fn fail(msg: String) -> Self {
+        Self::fail_with_code(-1, msg)
+    }
+
+    pub fn fail_with_code(code: i32, msg: String) -> Self {
         Self {
-            code: -1,
+            code,
             msg,
             data: None,
         }
     }
+
+    pub fn fail_with_data(code: i32, msg: String, data: T) -> Self {
+        Self {
+            code,
+            msg,
+            data: Option::Some(data),
+        }
+    }
 }
@@ -1,10 +1,14 @@
-use crate::dto::auth_dto::RoleRelPathDto;
-use dashmap::DashMap;
+use crate::service::{
+    login_throttle_service::LoginAttemptTracker, permission_service::PermissionMatcher,
+    token_revocation_service::TokenRevocationStore,
+};
 use rbatis::RBatis;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 #[derive(Clone, Debug)]
 pub struct AppState {
     pub db_pool: RBatis,
-    pub role_rel_resource_map: Arc<DashMap<String, Vec<RoleRelPathDto>>>,
+    pub permission_matcher: Arc<RwLock<PermissionMatcher>>,
+    pub revocation_store: Arc<TokenRevocationStore>,
+    pub login_tracker: Arc<LoginAttemptTracker>,
 }
@@ -0,0 +1,116 @@
+use rbatis::{self, RBatis, impl_select, rbdc::DateTime, rbdc::db::ExecResult, sql};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshToken {
+    pub id: Option<u64>,
+    pub create_time: Option<DateTime>,
+    pub creator_id: Option<u64>,
+    pub update_time: Option<DateTime>,
+    pub updater_id: Option<u64>,
+    pub is_delete: Option<u8>,
+    pub del_unique_key: Option<u64>,
+    pub user_id: Option<u64>,
+    pub token_hash: Option<String>, // 刷新令牌的sha256摘要，不保存明文
+    pub family_id: Option<String>,  // 同一次登录轮换出的刷新令牌属于同一家族
+    pub expire_time: Option<DateTime>,
+    pub is_used: Option<u8>, // 0 未使用 1 已轮换
+}
+impl RefreshToken {
+    pub fn init_create(
+        user_id: u64,
+        token_hash: String,
+        family_id: String,
+        expire_time: DateTime,
+    ) -> Self {
+        RefreshToken {
+            id: None,
+            create_time: Some(DateTime::now()),
+            creator_id: Some(user_id),
+            update_time: None,
+            updater_id: None,
+            is_delete: Some(0),
+            del_unique_key: Some(0),
+            user_id: Some(user_id),
+            token_hash: Some(token_hash),
+            family_id: Some(family_id),
+            expire_time: Some(expire_time),
+            is_used: Some(0),
+        }
+    }
+}
+
+rbatis::crud!(RefreshToken {}, "sys_refresh_token");
+impl_select!(RefreshToken{select_by_token_hash(token_hash:&str) -> Option => "`where token_hash = #{token_hash} and is_delete = 0 limit 1`"}, "sys_refresh_token");
+
+// 将刷新令牌标记为已使用，仅当其尚未使用时才会成功，用于并发下的轮换判定
+#[sql(
+    "update sys_refresh_token
+    set is_used = 1, update_time = now()
+    where id = ? and is_used = 0 and is_delete = 0"
+)]
+pub async fn mark_refresh_token_used(rb: &RBatis, id: u64) -> ExecResult {}
+
+// 作废整个刷新令牌家族，用于检测到令牌重放时
+#[sql(
+    "update sys_refresh_token
+    set is_delete = 1, update_time = now()
+    where family_id = ? and is_delete = 0"
+)]
+pub async fn revoke_refresh_token_family(rb: &RBatis, family_id: &str) -> ExecResult {}
+
+// 按用户作废其全部刷新令牌，用于退出全部登录
+#[sql(
+    "update sys_refresh_token
+    set is_delete = 1, update_time = now()
+    where user_id = ? and is_delete = 0"
+)]
+pub async fn revoke_refresh_token_by_user_id(rb: &RBatis, user_id: u64) -> ExecResult {}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RevokedToken {
+    pub id: Option<u64>,
+    pub create_time: Option<DateTime>,
+    pub creator_id: Option<u64>,
+    pub update_time: Option<DateTime>,
+    pub updater_id: Option<u64>,
+    pub is_delete: Option<u8>,
+    pub del_unique_key: Option<u64>,
+    pub user_id: Option<u64>,
+    pub jti: Option<String>,           // 作废单个令牌时的令牌ID
+    pub revoke_type: Option<u8>,       // 1 单个令牌 2 用户全部令牌
+    pub revoke_before: Option<u64>,    // 作废用户全部令牌时，签发时间早于该时间戳的令牌失效
+    pub expire_time: Option<DateTime>, // 超过该时间后记录可被清理
+}
+impl RevokedToken {
+    pub fn init_create(
+        user_id: u64,
+        jti: Option<String>,
+        revoke_type: u8,
+        revoke_before: Option<u64>,
+        expire_time: DateTime,
+    ) -> Self {
+        RevokedToken {
+            id: None,
+            create_time: Some(DateTime::now()),
+            creator_id: Some(user_id),
+            update_time: None,
+            updater_id: None,
+            is_delete: Some(0),
+            del_unique_key: Some(0),
+            user_id: Some(user_id),
+            jti,
+            revoke_type: Some(revoke_type),
+            revoke_before,
+            expire_time: Some(expire_time),
+        }
+    }
+}
+
+rbatis::crud!(RevokedToken {}, "sys_revoked_token");
+impl_select!(RevokedToken{select_unexpired() => "`where is_delete = 0 and expire_time > now()`"}, "sys_revoked_token");
+impl_select!(RevokedToken{select_unexpired_by_jti_or_user(jti:&str, user_id:u64) => "`where is_delete = 0 and expire_time > now() and (jti = #{jti} or (revoke_type = 2 and user_id = #{user_id}))`"}, "sys_revoked_token");
+
+// 清理已过原始有效期的作废记录
+#[sql("delete from sys_revoked_token where expire_time <= now()")]
+pub async fn delete_expired_revoked_token(rb: &RBatis) -> ExecResult {}
@@ -1,9 +1,21 @@
-use rbatis::{self, RBatis, impl_select, rbdc::DateTime, sql};
+// rbatis 的 #[sql] 宏生成的查询函数会包含 Ok(..?)
+#![allow(clippy::needless_question_mark)]
+use rbatis::{
+    self, RBatis, impl_select, impl_select_page, py_sql,
+    rbdc::{DateTime, db::ExecResult},
+    sql,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::dto::auth_dto::RoleRelPathDto;
+use crate::dto::{
+    auth_dto::{RoleInheritDto, RoleRelPathDto},
+    resource_dto::ResourceQueryDto,
+    role_dto::RoleQueryDto,
+    user_dto::UserQueryDto,
+};
+use crate::model::constance::{TOTP_DISABLED, USER_STATUS_ENABLED};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct User {
     pub id: Option<u64>,
     pub create_time: Option<DateTime>,
@@ -11,10 +23,15 @@ pub struct User {
     pub update_time: Option<DateTime>,
     pub updater_id: Option<u64>,
     pub is_delete: Option<u8>,
-    pub del_unique_key: Option<i8>,
+    pub del_unique_key: Option<u64>, // 未删除时为0，软删除时置为id，以释放用户名唯一索引
     pub username: Option<String>,
     pub password: Option<String>,
     pub name: Option<String>,
+    pub status: Option<u8>,          // 0 禁用 1 启用
+    pub auth_version: Option<u64>, // 权限版本号，角色或密码变更时递增，令牌中的版本号低于该值时失效
+    pub totp_secret: Option<String>, // TOTP密钥，Base32编码；开始绑定后即保存，确认后才启用
+    pub totp_enabled: Option<u8>,  // 0 未启用 1 已启用两步验证
+    pub totp_last_step: Option<u64>, // 最近一次校验通过的时间步，用于拒绝重复使用的验证码
 }
 impl User {
     pub fn init_create(username: String, password: String, name: String) -> Self {
@@ -29,14 +46,37 @@ impl User {
             username: Some(username),
             password: Some(password),
             name: Some(name),
+            status: Some(USER_STATUS_ENABLED),
+            auth_version: Some(0),
+            totp_secret: None,
+            totp_enabled: Some(TOTP_DISABLED),
+            totp_last_step: None,
         }
     }
 }
 
 rbatis::crud!(User {}, "sys_user");
-impl_select!(User{select_by_username(username:String) -> Option => "`where username = #{username} limit 1`"}, "sys_user");
+impl_select!(User{select_by_username(username:String) -> Option => "`where username = #{username} and is_delete = 0 limit 1`"}, "sys_user");
+impl_select!(User{select_by_id(id:u64) -> Option => "`where id = #{id} and is_delete = 0 limit 1`"}, "sys_user");
+impl_select_page!(User{select_page_by_query(query:&UserQueryDto) => "
+ `where is_delete = 0`
+ if query.username != null && query.username != '':
+   ` and username like concat('%', #{query.username}, '%')`
+ if query.name != null && query.name != '':
+   ` and name like concat('%', #{query.name}, '%')`
+ if query.status != null:
+   ` and status = #{query.status}`
+ ` order by id desc`"}, "sys_user");
 
-#[derive(Debug, Serialize, Deserialize)]
+// 软删除用户，同时把del_unique_key置为id，使用户名可以被重新注册
+#[sql(
+    "update sys_user
+    set is_delete = 1, del_unique_key = id, updater_id = ?, update_time = now()
+    where id = ? and is_delete = 0"
+)]
+pub async fn soft_delete_user(rb: &RBatis, updater_id: u64, id: u64) -> ExecResult {}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Role {
     pub id: Option<u64>,
     pub create_time: Option<DateTime>,
@@ -44,11 +84,112 @@ pub struct Role {
     pub update_time: Option<DateTime>,
     pub updater_id: Option<u64>,
     pub is_delete: Option<u8>,
-    pub del_unique_key: Option<i8>,
+    pub del_unique_key: Option<u64>,
     pub role_name: Option<String>,
     pub role_desc: Option<String>,
 }
+impl Role {
+    pub fn init_create(role_name: String, role_desc: Option<String>, creator_id: u64) -> Self {
+        Role {
+            id: None,
+            create_time: Some(DateTime::now()),
+            creator_id: Some(creator_id),
+            update_time: None,
+            updater_id: None,
+            is_delete: Some(0),
+            del_unique_key: Some(0),
+            role_name: Some(role_name),
+            role_desc,
+        }
+    }
+}
 rbatis::crud!(Role {}, "sys_role");
+impl_select!(Role{select_by_id(id:u64) -> Option => "`where id = #{id} and is_delete = 0 limit 1`"}, "sys_role");
+impl_select!(Role{select_by_role_name(role_name:&str) -> Option => "`where role_name = #{role_name} and is_delete = 0 limit 1`"}, "sys_role");
+impl_select_page!(Role{select_page_by_query(query:&RoleQueryDto) => "
+ `where is_delete = 0`
+ if query.role_name != null && query.role_name != '':
+   ` and role_name like concat('%', #{query.role_name}, '%')`
+ ` order by id desc`"}, "sys_role");
+
+// 记录校验通过的TOTP时间步，时间步不大于已记录的值时不更新，说明验证码被重复使用
+#[sql(
+    "update sys_user
+    set totp_last_step = ?
+    where id = ? and (totp_last_step is null or totp_last_step < ?)"
+)]
+pub async fn update_totp_last_step(rb: &RBatis, step: u64, id: u64, min_step: u64) -> ExecResult {}
+
+// 更新密码哈希，密码在此期间被修改时不更新
+#[sql(
+    "update sys_user
+    set password = ?
+    where id = ? and password = ?"
+)]
+pub async fn update_password_hash(
+    rb: &RBatis,
+    password: &str,
+    id: u64,
+    old_password: &str,
+) -> ExecResult {
+}
+
+// 关闭两步验证并清除密钥
+#[sql(
+    "update sys_user
+    set totp_secret = null, totp_enabled = 0, totp_last_step = null, updater_id = ?, update_time = now()
+    where id = ? and is_delete = 0"
+)]
+pub async fn clear_user_totp(rb: &RBatis, updater_id: u64, id: u64) -> ExecResult {}
+
+// 递增用户的权限版本号，使该用户已签发的令牌失效
+#[sql(
+    "update sys_user
+    set auth_version = auth_version + 1
+    where id = ?"
+)]
+pub async fn bump_user_auth_version(rb: &RBatis, id: u64) -> ExecResult {}
+
+// 递增拥有该角色的全部用户的权限版本号
+#[sql(
+    "update sys_user
+    set auth_version = auth_version + 1
+    where id in (select user_id from sys_user_rel_role where role_id = ? and is_delete = 0)"
+)]
+pub async fn bump_auth_version_by_role_id(rb: &RBatis, role_id: u64) -> ExecResult {}
+
+// 软删除角色，同时把del_unique_key置为id，使角色名可以被重新使用
+#[sql(
+    "update sys_role
+    set is_delete = 1, del_unique_key = id, updater_id = ?, update_time = now()
+    where id = ? and is_delete = 0"
+)]
+pub async fn soft_delete_role(rb: &RBatis, updater_id: u64, id: u64) -> ExecResult {}
+
+// 软删除角色与用户、资源的全部关联
+#[sql(
+    "update sys_user_rel_role
+    set is_delete = 1, del_unique_key = id, updater_id = ?, update_time = now()
+    where role_id = ? and is_delete = 0"
+)]
+pub async fn soft_delete_user_rel_role_by_role_id(
+    rb: &RBatis,
+    updater_id: u64,
+    role_id: u64,
+) -> ExecResult {
+}
+
+#[sql(
+    "update sys_role_rel_resource
+    set is_delete = 1, del_unique_key = id, updater_id = ?, update_time = now()
+    where role_id = ? and is_delete = 0"
+)]
+pub async fn soft_delete_role_rel_resource_by_role_id(
+    rb: &RBatis,
+    updater_id: u64,
+    role_id: u64,
+) -> ExecResult {
+}
 
 #[sql(
     "select sr.*
@@ -59,6 +200,16 @@ rbatis::crud!(Role {}, "sys_role");
 )]
 pub async fn select_roles_by_user_id(rb: &RBatis, user_id: u64) -> Vec<Role> {}
 
+#[sql(
+    "select su.*
+    from sys_user_rel_role surr
+    join sys_user su on su.id = surr.user_id and su.is_delete = 0
+    where surr.is_delete = 0
+      and surr.role_id = ?
+    order by su.id"
+)]
+pub async fn select_users_by_role_id(rb: &RBatis, role_id: u64) -> Vec<User> {}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserRelRole {
     pub id: Option<u64>,
@@ -67,13 +218,43 @@ pub struct UserRelRole {
     pub update_time: Option<DateTime>,
     pub updater_id: Option<u64>,
     pub is_delete: Option<u8>,
-    pub del_unique_key: Option<i8>,
+    pub del_unique_key: Option<u64>,
     pub user_id: Option<u64>,
     pub role_id: Option<u64>,
 }
+impl UserRelRole {
+    pub fn init_create(user_id: u64, role_id: u64, creator_id: u64) -> Self {
+        UserRelRole {
+            id: None,
+            create_time: Some(DateTime::now()),
+            creator_id: Some(creator_id),
+            update_time: None,
+            updater_id: None,
+            is_delete: Some(0),
+            del_unique_key: Some(0),
+            user_id: Some(user_id),
+            role_id: Some(role_id),
+        }
+    }
+}
 rbatis::crud!(UserRelRole {}, "sys_user_rel_role");
+impl_select!(UserRelRole{select_by_user_and_role(user_id:u64, role_id:u64) -> Option => "`where user_id = #{user_id} and role_id = #{role_id} and is_delete = 0 limit 1`"}, "sys_user_rel_role");
 
-#[derive(Debug, Serialize, Deserialize)]
+// 取消用户的某个角色
+#[sql(
+    "update sys_user_rel_role
+    set is_delete = 1, del_unique_key = id, updater_id = ?, update_time = now()
+    where user_id = ? and role_id = ? and is_delete = 0"
+)]
+pub async fn soft_delete_user_rel_role(
+    rb: &RBatis,
+    updater_id: u64,
+    user_id: u64,
+    role_id: u64,
+) -> ExecResult {
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Resource {
     pub id: Option<u64>,
     pub create_time: Option<DateTime>,
@@ -81,13 +262,98 @@ pub struct Resource {
     pub update_time: Option<DateTime>,
     pub updater_id: Option<u64>,
     pub is_delete: Option<u8>,
-    pub del_unique_key: Option<i8>,
+    pub del_unique_key: Option<u64>,
     pub resource_value: Option<String>,
     pub resource_desc: Option<String>,
-    pub resource_type: Option<u8>, // 1 path 2 page 3 button
+    pub resource_type: Option<u8>,       // 1 path 2 page 3 button
+    pub resource_method: Option<String>, // 允许的请求方法，逗号分隔，如 GET,POST；为空或 * 表示全部方法
     pub parent_id: Option<u64>,
 }
+impl Resource {
+    pub fn init_create(
+        resource_value: String,
+        resource_desc: Option<String>,
+        resource_type: u8,
+        resource_method: Option<String>,
+        parent_id: Option<u64>,
+        creator_id: u64,
+    ) -> Self {
+        Resource {
+            id: None,
+            create_time: Some(DateTime::now()),
+            creator_id: Some(creator_id),
+            update_time: None,
+            updater_id: None,
+            is_delete: Some(0),
+            del_unique_key: Some(0),
+            resource_value: Some(resource_value),
+            resource_desc,
+            resource_type: Some(resource_type),
+            resource_method,
+            parent_id,
+        }
+    }
+}
 rbatis::crud!(Resource {}, "sys_resource");
+impl_select!(Resource{select_by_id(id:u64) -> Option => "`where id = #{id} and is_delete = 0 limit 1`"}, "sys_resource");
+impl_select!(Resource{select_by_parent_id(parent_id:u64) => "`where parent_id = #{parent_id} and is_delete = 0`"}, "sys_resource");
+impl_select_page!(Resource{select_page_by_query(query:&ResourceQueryDto) => "
+ `where is_delete = 0`
+ if query.resource_type != null:
+   ` and resource_type = #{query.resource_type}`
+ if query.resource_value != null && query.resource_value != '':
+   ` and resource_value like concat('%', #{query.resource_value}, '%')`
+ if query.parent_id != null:
+   ` and parent_id = #{query.parent_id}`
+ ` order by id desc`"}, "sys_resource");
+
+// 取消资源的上级资源
+#[sql(
+    "update sys_resource
+    set parent_id = null, updater_id = ?, update_time = now()
+    where id = ? and is_delete = 0"
+)]
+pub async fn clear_resource_parent(rb: &RBatis, updater_id: u64, id: u64) -> ExecResult {}
+
+// 软删除资源，同时把del_unique_key置为id
+#[sql(
+    "update sys_resource
+    set is_delete = 1, del_unique_key = id, updater_id = ?, update_time = now()
+    where id = ? and is_delete = 0"
+)]
+pub async fn soft_delete_resource(rb: &RBatis, updater_id: u64, id: u64) -> ExecResult {}
+
+#[sql(
+    "select sre.*
+    from sys_role_rel_resource srrr
+    join sys_resource sre on sre.id = srrr.resource_id and sre.is_delete = 0
+    where srrr.is_delete = 0
+      and srrr.role_id = ?
+    order by sre.id"
+)]
+pub async fn select_resources_by_role_id(rb: &RBatis, role_id: u64) -> Vec<Resource> {}
+
+// 查询角色列表被授权的指定类型的资源，同一资源只返回一次
+#[py_sql(
+    "`select distinct sre.*
+    from sys_role sr
+    join sys_role_rel_resource srrr on sr.id = srrr.role_id and srrr.is_delete = 0
+    join sys_resource sre on sre.id = srrr.resource_id and sre.is_delete = 0
+    where sr.is_delete = 0 and sre.resource_type in (`
+    trim ',': for _,resource_type in resource_types:
+      #{resource_type},
+    `) and sr.role_name in (`
+    trim ',': for _,role_name in role_names:
+      #{role_name},
+    `) order by sre.id`"
+)]
+pub async fn select_resources_by_role_names(
+    rb: &RBatis,
+    role_names: &[String],
+    resource_types: &[u8],
+) -> rbatis::Result<Vec<Resource>> {
+    impled!()
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RoleRelResource {
@@ -97,17 +363,187 @@ pub struct RoleRelResource {
     pub update_time: Option<DateTime>,
     pub updater_id: Option<u64>,
     pub is_delete: Option<u8>,
-    pub del_unique_key: Option<i8>,
+    pub del_unique_key: Option<u64>,
     pub role_id: Option<u64>,
     pub resource_id: Option<u64>,
 }
+impl RoleRelResource {
+    pub fn init_create(role_id: u64, resource_id: u64, creator_id: u64) -> Self {
+        RoleRelResource {
+            id: None,
+            create_time: Some(DateTime::now()),
+            creator_id: Some(creator_id),
+            update_time: None,
+            updater_id: None,
+            is_delete: Some(0),
+            del_unique_key: Some(0),
+            role_id: Some(role_id),
+            resource_id: Some(resource_id),
+        }
+    }
+}
 rbatis::crud!(RoleRelResource {}, "sys_role_rel_resource");
+impl_select!(RoleRelResource{select_by_role_and_resource(role_id:u64, resource_id:u64) -> Option => "`where role_id = #{role_id} and resource_id = #{resource_id} and is_delete = 0 limit 1`"}, "sys_role_rel_resource");
+
+// 取消角色的某个资源授权
+#[sql(
+    "update sys_role_rel_resource
+    set is_delete = 1, del_unique_key = id, updater_id = ?, update_time = now()
+    where role_id = ? and resource_id = ? and is_delete = 0"
+)]
+pub async fn soft_delete_role_rel_resource(
+    rb: &RBatis,
+    updater_id: u64,
+    role_id: u64,
+    resource_id: u64,
+) -> ExecResult {
+}
 
 #[sql(
-    "select sr.role_name, sre.resource_value
+    "update sys_role_rel_resource
+    set is_delete = 1, del_unique_key = id, updater_id = ?, update_time = now()
+    where resource_id = ? and is_delete = 0"
+)]
+pub async fn soft_delete_role_rel_resource_by_resource_id(
+    rb: &RBatis,
+    updater_id: u64,
+    resource_id: u64,
+) -> ExecResult {
+}
+
+#[sql(
+    "select sr.role_name, sre.resource_value, sre.resource_method
 from sys_role sr
          join sys_role_rel_resource srrr on sr.id = srrr.role_id and srrr.is_delete = 0
-         join sys_resource sre on sre.id = srrr.resourcec_id and sre.is_delete = 0
+         join sys_resource sre on sre.id = srrr.resource_id and sre.is_delete = 0
 where sr.is_delete = 0 and sre.resource_type = 1"
 )]
 pub async fn select_all_role_rel_path(rb: &RBatis) -> Vec<RoleRelPathDto> {}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoleInherit {
+    pub id: Option<u64>,
+    pub create_time: Option<DateTime>,
+    pub creator_id: Option<u64>,
+    pub update_time: Option<DateTime>,
+    pub updater_id: Option<u64>,
+    pub is_delete: Option<u8>,
+    pub del_unique_key: Option<u64>,
+    pub role_id: Option<u64>,
+    pub parent_role_id: Option<u64>,
+}
+impl RoleInherit {
+    pub fn init_create(role_id: u64, parent_role_id: u64, creator_id: u64) -> Self {
+        RoleInherit {
+            id: None,
+            create_time: Some(DateTime::now()),
+            creator_id: Some(creator_id),
+            update_time: None,
+            updater_id: None,
+            is_delete: Some(0),
+            del_unique_key: Some(0),
+            role_id: Some(role_id),
+            parent_role_id: Some(parent_role_id),
+        }
+    }
+}
+rbatis::crud!(RoleInherit {}, "sys_role_inherit");
+impl_select!(RoleInherit{select_by_role_and_parent(role_id:u64, parent_role_id:u64) -> Option => "`where role_id = #{role_id} and parent_role_id = #{parent_role_id} and is_delete = 0 limit 1`"}, "sys_role_inherit");
+
+// 取消角色对某个父角色的继承
+#[sql(
+    "update sys_role_inherit
+    set is_delete = 1, del_unique_key = id, updater_id = ?, update_time = now()
+    where role_id = ? and parent_role_id = ? and is_delete = 0"
+)]
+pub async fn soft_delete_role_inherit(
+    rb: &RBatis,
+    updater_id: u64,
+    role_id: u64,
+    parent_role_id: u64,
+) -> ExecResult {
+}
+
+// 软删除角色作为子角色或父角色的全部继承关系
+#[sql(
+    "update sys_role_inherit
+    set is_delete = 1, del_unique_key = id, updater_id = ?, update_time = now()
+    where (role_id = ? or parent_role_id = ?) and is_delete = 0"
+)]
+pub async fn soft_delete_role_inherit_by_role_id(
+    rb: &RBatis,
+    updater_id: u64,
+    role_id: u64,
+    parent_role_id: u64,
+) -> ExecResult {
+}
+
+#[sql(
+    "select pr.*
+    from sys_role_inherit sri
+    join sys_role pr on pr.id = sri.parent_role_id and pr.is_delete = 0
+    where sri.is_delete = 0
+      and sri.role_id = ?"
+)]
+pub async fn select_parent_roles_by_role_id(rb: &RBatis, role_id: u64) -> Vec<Role> {}
+
+#[sql(
+    "select sr.role_name, pr.role_name as parent_role_name
+from sys_role_inherit sri
+         join sys_role sr on sr.id = sri.role_id and sr.is_delete = 0
+         join sys_role pr on pr.id = sri.parent_role_id and pr.is_delete = 0
+where sri.is_delete = 0"
+)]
+pub async fn select_all_role_inherit(rb: &RBatis) -> Vec<RoleInheritDto> {}
+
+// 两步验证的恢复码，只保存摘要，使用后软删除
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserRecoveryCode {
+    pub id: Option<u64>,
+    pub create_time: Option<DateTime>,
+    pub creator_id: Option<u64>,
+    pub update_time: Option<DateTime>,
+    pub updater_id: Option<u64>,
+    pub is_delete: Option<u8>,
+    pub del_unique_key: Option<u64>,
+    pub user_id: Option<u64>,
+    pub code_hash: Option<String>,
+}
+impl UserRecoveryCode {
+    pub fn init_create(user_id: u64, code_hash: String) -> Self {
+        UserRecoveryCode {
+            id: None,
+            create_time: Some(DateTime::now()),
+            creator_id: Some(user_id),
+            update_time: None,
+            updater_id: None,
+            is_delete: Some(0),
+            del_unique_key: Some(0),
+            user_id: Some(user_id),
+            code_hash: Some(code_hash),
+        }
+    }
+}
+rbatis::crud!(UserRecoveryCode {}, "sys_user_recovery_code");
+impl_select!(UserRecoveryCode{select_unused_by_user_id(user_id:u64) => "`where user_id = #{user_id} and is_delete = 0`"}, "sys_user_recovery_code");
+
+// 使用恢复码，恢复码不存在或已使用时影响行数为0
+#[sql(
+    "update sys_user_recovery_code
+    set is_delete = 1, del_unique_key = id, updater_id = user_id, update_time = now()
+    where user_id = ? and code_hash = ? and is_delete = 0"
+)]
+pub async fn use_recovery_code(rb: &RBatis, user_id: u64, code_hash: &str) -> ExecResult {}
+
+// 作废用户全部未使用的恢复码
+#[sql(
+    "update sys_user_recovery_code
+    set is_delete = 1, del_unique_key = id, updater_id = ?, update_time = now()
+    where user_id = ? and is_delete = 0"
+)]
+pub async fn soft_delete_recovery_codes_by_user_id(
+    rb: &RBatis,
+    updater_id: u64,
+    user_id: u64,
+) -> ExecResult {
+}
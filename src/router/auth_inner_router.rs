@@ -1,7 +1,8 @@
 use crate::{
     config::auth_middleware::Auth,
     handler::auth_inner_handler::{
-        get_current_loaded_role_rel_resource, reflush_all_role_rel_resource,
+        explain, get_current_loaded_role_rel_resource, permission_version,
+        reflush_all_role_rel_resource,
     },
 };
 use actix_web::web::{self, ServiceConfig};
@@ -11,6 +12,8 @@ pub fn routes(cfg: &mut ServiceConfig) {
         web::scope("/auth_inner")
             .service(reflush_all_role_rel_resource)
             .service(get_current_loaded_role_rel_resource)
+            .service(explain)
+            .service(permission_version)
             .wrap(Auth),
     );
 }
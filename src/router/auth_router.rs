@@ -1,6 +1,28 @@
-use crate::handler::auth_handler::{login, register};
+use crate::config::auth_middleware::Auth;
+use crate::handler::auth_handler::{
+    change_password, confirm_totp, disable_totp, login, login_totp, logout, logout_all, me,
+    my_menus, refresh, regenerate_recovery_codes, register, setup_totp, totp_status,
+};
 use actix_web::web::{self, ServiceConfig};
 
 pub fn routes(cfg: &mut ServiceConfig) {
-    cfg.service(web::scope("/auth").service(login).service(register));
+    cfg.service(
+        web::scope("/auth")
+            .service(login)
+            .service(login_totp)
+            .service(register)
+            .service(refresh)
+            .service(logout)
+            .service(logout_all)
+            .service(me)
+            .service(change_password)
+            .service(my_menus)
+            .service(totp_status)
+            .service(setup_totp)
+            .service(confirm_totp)
+            .service(disable_totp)
+            .service(regenerate_recovery_codes)
+            // 登录、注册、刷新为内置公开路径，其余接口为内置的仅需登录路径
+            .wrap(Auth),
+    );
 }
@@ -1,3 +1,7 @@
 pub mod auth_inner_router;
 pub mod auth_router;
+pub mod resource_router;
+pub mod role_router;
 pub mod test_router;
+pub mod user_router;
+pub mod well_known_router;
@@ -0,0 +1,21 @@
+use crate::config::auth_middleware::Auth;
+use crate::handler::resource_handler::{
+    create_resource, delete_resource, get_resource, grant_resources, list_role_resources,
+    page_resources, revoke_resource, update_resource,
+};
+use actix_web::web::{self, ServiceConfig};
+
+pub fn routes(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::scope("/sys/resource")
+            .service(page_resources)
+            .service(create_resource)
+            .service(list_role_resources)
+            .service(grant_resources)
+            .service(revoke_resource)
+            .service(get_resource)
+            .service(update_resource)
+            .service(delete_resource)
+            .wrap(Auth),
+    );
+}
@@ -0,0 +1,25 @@
+use crate::config::auth_middleware::Auth;
+use crate::handler::role_handler::{
+    add_parent_role, assign_role, create_role, delete_role, get_role, list_parent_roles,
+    list_role_users, list_user_roles, page_roles, remove_parent_role, revoke_role, update_role,
+};
+use actix_web::web::{self, ServiceConfig};
+
+pub fn routes(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::scope("/sys/role")
+            .service(page_roles)
+            .service(create_role)
+            .service(list_user_roles)
+            .service(get_role)
+            .service(update_role)
+            .service(delete_role)
+            .service(list_role_users)
+            .service(assign_role)
+            .service(revoke_role)
+            .service(list_parent_roles)
+            .service(add_parent_role)
+            .service(remove_parent_role)
+            .wrap(Auth),
+    );
+}
@@ -0,0 +1,21 @@
+use crate::config::auth_middleware::Auth;
+use crate::handler::user_handler::{
+    delete_user, disable_user, enable_user, get_user, page_users, reset_password, reset_totp,
+    update_user,
+};
+use actix_web::web::{self, ServiceConfig};
+
+pub fn routes(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::scope("/sys/user")
+            .service(page_users)
+            .service(get_user)
+            .service(update_user)
+            .service(enable_user)
+            .service(disable_user)
+            .service(delete_user)
+            .service(reset_password)
+            .service(reset_totp)
+            .wrap(Auth),
+    );
+}
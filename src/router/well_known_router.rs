@@ -0,0 +1,6 @@
+use crate::handler::jwks_handler::jwks;
+use actix_web::web::{self, ServiceConfig};
+
+pub fn routes(cfg: &mut ServiceConfig) {
+    cfg.service(web::scope("/.well-known").service(jwks));
+}
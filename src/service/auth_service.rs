@@ -1,65 +1,358 @@
-use crate::dto::auth_dto::{RegisterDto, RoleRelPathDto};
-use crate::model::user::{select_all_role_rel_path, select_roles_by_user_id};
-use crate::model::{constance::DEFAULT_BCRYPT_COST, user::User};
+use crate::dto::{
+    auth_dto::{
+        ChangePasswordDto, LoginVo, PermissionDecision, PermissionExplainDto,
+        PermissionExplainQueryDto, PermissionSnapshotDto, PermissionVersionDto, ProfileVo,
+        RegisterDto, TokenPairDto, TotpChallengeVo, TotpLoginDto,
+    },
+    user_dto::UserVo,
+};
+use crate::model::permission::{PermissionVersion, bump_permission_version};
+use crate::model::token::{RefreshToken, mark_refresh_token_used, revoke_refresh_token_family};
+use crate::model::user::{
+    select_all_role_inherit, select_all_role_rel_path, select_roles_by_user_id,
+    update_password_hash,
+};
+use crate::model::{
+    constance::{AUDIT_LOG_TARGET, USER_STATUS_DISABLED},
+    error::{AppError, AppResult},
+    user::User,
+};
+use crate::service::{
+    login_throttle_service::LoginAttemptTracker,
+    password_policy_service,
+    permission_service::{ACCESS_RULES, AccessLevel, PermissionMatcher},
+    token_revocation_service::TokenRevocationStore,
+    totp_service, user_service,
+};
 use crate::util::{
     config_util::CFG,
-    token_util::{generate_jwt_token, validate_jwt_token},
+    password_util::{PASSWORD_HASHER, hash_password, verify_password},
+    token_util::{
+        ChallengeClaims, TokenClaims, generate_challenge_token, generate_jwt_token,
+        generate_opaque_token, hash_opaque_token, validate_challenge_token, validate_jwt_token,
+    },
 };
-use bcrypt;
 use chrono::{TimeDelta, Utc};
-use dashmap::DashMap;
+use jsonwebtoken::errors::ErrorKind;
+use lazy_static::lazy_static;
 use log::{debug, error, info, warn};
-use rbatis::RBatis;
-use regex::Regex;
-use std::collections::HashMap;
+use rbatis::{RBatis, rbdc::DateTime};
+use std::sync::{PoisonError, RwLock};
 
 /// 异步验证用户登录
 ///
-/// 该函数通过用户名和密码验证用户身份，并返回用户ID
-/// 它首先根据用户名查询用户信息，然后使用bcrypt算法验证密码
+/// 该函数通过用户名和密码验证用户身份，并返回登录结果
+/// 它首先检查用户名与客户端IP是否因连续登录失败被锁定，然后根据用户名查询用户信息并使用bcrypt算法验证密码。
+/// 未启用两步验证的用户直接签发令牌；已启用的用户返回登录挑战，需再调用`verify_totp_login`提交动态验证码
 ///
 /// 参数:
 /// - `rb`: &RBatis - 数据库连接实例引用，用于执行数据库操作
+/// - `login_tracker`: &LoginAttemptTracker - 登录失败计数器
 /// - `username`: &str - 用户名字符串引用，用于查询用户信息
 /// - `password`: &str - 密码字符串引用，用于验证用户密码
+/// - `ip`: Option<&str> - 客户端IP
 ///
 /// 返回:
-/// - `Result<u64, String>` - 如果登录成功，返回用户ID（u64）；
-///   如果登录失败，返回错误信息（String）
-pub async fn verify_login(rb: &RBatis, username: &str, password: &str) -> Result<u64, String> {
-    // 根据用户名查询用户信息
-    match User::select_by_username(rb, username.to_string()).await {
-        Ok(user_option) => {
-            if let Some(user) = user_option {
-                // 验证用户密码
-                match bcrypt::verify(password, user.password.as_deref().unwrap_or("")) {
-                    Ok(valid) => {
-                        if valid {
-                            // 如果密码正确，返回用户ID
-                            user.id.ok_or_else(|| "用户ID为空".to_string())
-                        } else {
-                            // 如果密码不正确，返回错误信息
-                            Err("密码错误".to_string())
-                        }
-                    }
-                    Err(_) => {
-                        // 如果密码验证失败，返回错误信息
-                        Err("密码验证失败".to_string())
-                    }
-                }
-            } else {
-                // 如果用户不存在，返回错误信息
-                Err("用户不存在".to_string())
+/// - `AppResult<LoginVo>` - 如果登录成功，返回令牌对或登录挑战；
+///   如果用户不存在或密码错误，返回`AppError::InvalidCredentials`；
+///   如果被锁定，返回`AppError::AccountLocked`
+pub async fn verify_login(
+    rb: &RBatis,
+    login_tracker: &LoginAttemptTracker,
+    username: &str,
+    password: &str,
+    ip: Option<&str>,
+) -> AppResult<LoginVo> {
+    // 锁定期间不再校验密码，避免暴力猜测与bcrypt带来的CPU消耗
+    let result = match login_tracker.check(username, ip) {
+        Ok(()) => check_credentials(rb, username, password).await,
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(user) => {
+            let (user_id, username) = user_identity(&user)?;
+            if totp_service::is_enabled(&user) {
+                // 尚未通过第二因素，不清除失败记录，避免通过反复登录重置动态验证码的失败次数
+                info!(
+                    target: AUDIT_LOG_TARGET,
+                    "密码校验通过，等待动态验证码: user_id={}, ip={}",
+                    user_id,
+                    ip.unwrap_or("-")
+                );
+                return issue_challenge(user_id, &username).map(LoginVo::Challenge);
+            }
+            login_tracker.record_success(&username);
+            info!(
+                target: AUDIT_LOG_TARGET,
+                "登录成功: user_id={}, ip={}",
+                user_id,
+                ip.unwrap_or("-")
+            );
+            gen_token(rb, user_id, &username).await.map(LoginVo::Token)
+        }
+        Err(err) => {
+            if matches!(err, AppError::InvalidCredentials) {
+                login_tracker.record_failure(username, ip);
             }
+            // 失败原因不区分用户不存在与密码错误，用户名只记录摘要，便于关联同一用户名的多次尝试
+            info!(
+                target: AUDIT_LOG_TARGET,
+                "登录失败: reason={}, username_hash={}, ip={}",
+                LoginFailureReason::from(&err).as_str(),
+                username_fingerprint(username),
+                ip.unwrap_or("-")
+            );
+            Err(err)
+        }
+    }
+}
+
+/// 两步验证登录的第二步，使用登录挑战令牌与动态验证码（或恢复码）换取令牌对
+///
+/// 动态验证码错误与密码错误共用同一个登录失败计数器
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `login_tracker` - 登录失败计数器
+/// * `dto` - 登录挑战令牌与动态验证码
+/// * `ip` - 客户端IP
+///
+/// # 返回
+/// * 挑战令牌无效或过期时返回`AppError::InvalidToken`，验证码错误时返回`AppError::InvalidOtp`
+pub async fn verify_totp_login(
+    rb: &RBatis,
+    login_tracker: &LoginAttemptTracker,
+    dto: &TotpLoginDto,
+    ip: Option<&str>,
+) -> AppResult<TokenPairDto> {
+    let claims = validate_challenge_token(&dto.challenge_token)
+        .map_err(|_| AppError::InvalidToken("登录挑战已失效".to_string()))?;
+    let result = match login_tracker.check(&claims.username, ip) {
+        Ok(()) => check_second_factor(rb, &claims, dto).await,
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(()) => {
+            login_tracker.record_success(&claims.username);
+            info!(
+                target: AUDIT_LOG_TARGET,
+                "登录成功: user_id={}, ip={}, mfa=totp",
+                claims.user_id,
+                ip.unwrap_or("-")
+            );
+            gen_token(rb, claims.user_id, &claims.username).await
         }
         Err(err) => {
-            // 如果查询用户信息失败，返回错误信息
-            Err(format!("查询用户失败：{}", err.to_string()))
+            if matches!(err, AppError::InvalidOtp) {
+                login_tracker.record_failure(&claims.username, ip);
+            }
+            info!(
+                target: AUDIT_LOG_TARGET,
+                "登录失败: reason={}, user_id={}, ip={}",
+                LoginFailureReason::from(&err).as_str(),
+                claims.user_id,
+                ip.unwrap_or("-")
+            );
+            Err(err)
+        }
+    }
+}
+
+// 校验登录挑战对应用户的第二因素
+async fn check_second_factor(
+    rb: &RBatis,
+    claims: &ChallengeClaims,
+    dto: &TotpLoginDto,
+) -> AppResult<()> {
+    let user = User::select_by_id(rb, claims.user_id).await?;
+    match user {
+        Some(user) if user.status == Some(USER_STATUS_DISABLED) => Err(AppError::AccountDisabled),
+        // 签发挑战后两步验证被关闭或用户被删除，需重新登录
+        Some(user) if totp_service::is_enabled(&user) => {
+            totp_service::verify_second_factor(
+                rb,
+                &user,
+                dto.code.as_deref(),
+                dto.recovery_code.as_deref(),
+            )
+            .await
+        }
+        _ => Err(AppError::InvalidToken("登录挑战已失效".to_string())),
+    }
+}
+
+// 签发登录挑战令牌
+fn issue_challenge(user_id: u64, username: &str) -> AppResult<TotpChallengeVo> {
+    let expires_in = CFG.totp.challenge_expiration_second;
+    let expiration_time = Utc::now()
+        .checked_add_signed(TimeDelta::seconds(expires_in))
+        .ok_or_else(|| AppError::Internal("时间计算失败".to_string()))?
+        .timestamp() as usize;
+    let challenge_token = generate_challenge_token(user_id, username, expiration_time)
+        .map_err(|err| AppError::Internal(format!("登录挑战生成失败：{}", err)))?;
+    Ok(TotpChallengeVo {
+        mfa_required: true,
+        challenge_token,
+        expires_in,
+    })
+}
+
+fn user_identity(user: &User) -> AppResult<(u64, String)> {
+    let user_id = user
+        .id
+        .ok_or_else(|| AppError::Internal("用户ID为空".to_string()))?;
+    Ok((user_id, user.username.clone().unwrap_or_default()))
+}
+
+// 登录失败原因，写入审计日志
+enum LoginFailureReason {
+    // 用户名或密码错误，不区分用户是否存在
+    InvalidCredentials,
+    AccountDisabled,
+    Locked,
+    // 动态验证码或恢复码错误
+    InvalidOtp,
+    Error,
+}
+
+impl LoginFailureReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LoginFailureReason::InvalidCredentials => "invalid_credentials",
+            LoginFailureReason::AccountDisabled => "account_disabled",
+            LoginFailureReason::Locked => "locked",
+            LoginFailureReason::InvalidOtp => "invalid_otp",
+            LoginFailureReason::Error => "error",
+        }
+    }
+}
+
+impl From<&AppError> for LoginFailureReason {
+    fn from(err: &AppError) -> Self {
+        match err {
+            AppError::InvalidCredentials => LoginFailureReason::InvalidCredentials,
+            AppError::AccountDisabled => LoginFailureReason::AccountDisabled,
+            AppError::AccountLocked(_) => LoginFailureReason::Locked,
+            AppError::InvalidOtp => LoginFailureReason::InvalidOtp,
+            _ => LoginFailureReason::Error,
+        }
+    }
+}
+
+// 用户名摘要，不在日志中保存用户输入的原始用户名
+fn username_fingerprint(username: &str) -> String {
+    let mut fingerprint = hash_opaque_token(&username.trim().to_lowercase());
+    fingerprint.truncate(12);
+    fingerprint
+}
+
+// 占位密码哈希的原文
+const DUMMY_PASSWORD: &str = "papudding-dummy-password";
+
+lazy_static! {
+    /// 占位密码哈希
+    ///
+    /// 用户不存在时使用该哈希校验密码，使登录耗时与用户存在时一致，避免通过响应时间枚举用户名。
+    /// 按配置的算法与参数计算，与新保存的密码哈希耗时一致；旧格式的哈希在用户登录时升级。
+    /// 用户不存在时无论校验结果如何都会拒绝登录，因此原文使用固定值即可
+    pub static ref DUMMY_PASSWORD_HASH: String =
+        PASSWORD_HASHER.hash(DUMMY_PASSWORD).expect("生成占位密码哈希失败");
+}
+
+// 校验用户名与密码，返回用户信息；用户存在与否都会执行一次完整的密码哈希校验
+async fn check_credentials(rb: &RBatis, username: &str, password: &str) -> AppResult<User> {
+    // 根据用户名查询用户信息
+    let user = User::select_by_username(rb, username.to_string()).await?;
+    let hash = user
+        .as_ref()
+        .and_then(|user| user.password.as_deref())
+        .filter(|hash| !hash.is_empty())
+        .unwrap_or(DUMMY_PASSWORD_HASH.as_str());
+    // 验证用户密码，哈希格式错误视为密码不正确
+    let verified = verify_password(password, hash).await.unwrap_or_else(|err| {
+        error!(
+            "用户 {:?} 的密码哈希无效: {}",
+            user.as_ref().and_then(|user| user.id),
+            err
+        );
+        false
+    });
+    match user {
+        // 如果用户不存在或密码不正确，返回相同的错误
+        None => Err(AppError::InvalidCredentials),
+        Some(_) if !verified => Err(AppError::InvalidCredentials),
+        // 如果密码正确但账号已禁用，拒绝登录
+        Some(user) if user.status == Some(USER_STATUS_DISABLED) => Err(AppError::AccountDisabled),
+        // 如果密码正确，返回用户信息
+        Some(user) => {
+            upgrade_password_hash(rb, &user, password).await;
+            Ok(user)
         }
     }
 }
 
-/// 生成JWT token
+/// 校验当前登录用户的密码，用于修改密码、关闭两步验证等敏感操作
+///
+/// 与登录共用失败计数器：被锁定时不再校验密码，密码错误计入失败次数，
+/// 避免持有令牌者绕过登录限制猜测密码。校验通过时不清除失败记录，由调用方在操作完成后清除
+///
+/// # 参数
+/// * `login_tracker` - 登录失败计数器
+/// * `user` - 当前登录用户
+/// * `password` - 待校验的明文密码
+/// * `ip` - 客户端IP
+///
+/// # 返回
+/// * 密码正确返回`Ok(true)`，错误返回`Ok(false)`；被锁定时返回`AppError::AccountLocked`
+pub(crate) async fn check_current_password(
+    login_tracker: &LoginAttemptTracker,
+    user: &User,
+    password: &str,
+    ip: Option<&str>,
+) -> AppResult<bool> {
+    let username = user.username.as_deref().unwrap_or_default();
+    login_tracker.check(username, ip)?;
+    // 哈希格式错误视为密码不正确
+    let verified = match user.password.as_deref() {
+        Some(hash) => verify_password(password, hash).await.unwrap_or(false),
+        None => false,
+    };
+    if !verified {
+        login_tracker.record_failure(username, ip);
+        info!(
+            target: AUDIT_LOG_TARGET,
+            "当前密码校验失败: user_id={}, ip={}",
+            user.id.unwrap_or_default(),
+            ip.unwrap_or("-")
+        );
+    }
+    Ok(verified)
+}
+
+// 密码哈希的算法或参数与当前配置不同时按当前配置重新计算，失败不影响登录
+async fn upgrade_password_hash(rb: &RBatis, user: &User, password: &str) {
+    let (Some(id), Some(old_hash)) = (user.id, user.password.as_deref()) else {
+        return;
+    };
+    if !PASSWORD_HASHER.needs_rehash(old_hash) {
+        return;
+    }
+    let result = match hash_password(password).await {
+        Ok(new_hash) => update_password_hash(rb, &new_hash, id, old_hash)
+            .await
+            .map_err(AppError::from),
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(_) => info!("用户 {} 的密码哈希已按当前配置重新计算", id),
+        Err(err) => error!("重新计算用户 {} 的密码哈希失败: {}", id, err),
+    }
+}
+
+/// 生成访问令牌与刷新令牌
+///
+/// 访问令牌为短期有效的JWT，刷新令牌为保存在服务端的不透明随机串，
+/// 每次登录都会开启一个新的刷新令牌家族
 ///
 /// # Arguments
 ///
@@ -69,38 +362,132 @@ pub async fn verify_login(rb: &RBatis, username: &str, password: &str) -> Result
 ///
 /// # Returns
 ///
-/// * `Ok(String)` - 生成的JWT token字符串
-/// * `Err(String)` - 生成token失败的原因
-pub async fn gen_token(rb: &RBatis, user_id: u64, username: &str) -> Result<String, String> {
+/// * `Ok(TokenPairDto)` - 生成的访问令牌与刷新令牌
+/// * `Err(AppError)` - 生成token失败的原因
+pub async fn gen_token(rb: &RBatis, user_id: u64, username: &str) -> AppResult<TokenPairDto> {
+    let family_id = generate_opaque_token();
+    issue_token_pair(rb, user_id, username, &family_id).await
+}
+
+/// 使用刷新令牌换取新的令牌对
+///
+/// 刷新令牌只能使用一次，使用后即被轮换为同一家族中的新令牌。
+/// 如果一个已经轮换过的刷新令牌被再次使用，说明令牌可能已经泄露，
+/// 此时会作废整个家族，迫使该会话重新登录
+///
+/// # Arguments
+///
+/// * `rb` - RBatis实例，用于数据库操作
+/// * `refresh_token` - 客户端持有的刷新令牌
+///
+/// # Returns
+///
+/// * `Ok(TokenPairDto)` - 新的访问令牌与刷新令牌
+/// * `Err(AppError)` - 刷新失败的原因
+pub async fn refresh_token(rb: &RBatis, refresh_token: &str) -> AppResult<TokenPairDto> {
+    // 1. 根据摘要查询刷新令牌
+    let token_hash = hash_opaque_token(refresh_token);
+    let Some(stored) = RefreshToken::select_by_token_hash(rb, &token_hash).await? else {
+        return Err(AppError::InvalidRefreshToken("刷新令牌无效".to_string()));
+    };
+    let (Some(id), Some(user_id), Some(family_id)) =
+        (stored.id, stored.user_id, stored.family_id.clone())
+    else {
+        return Err(AppError::Internal("刷新令牌数据不完整".to_string()));
+    };
+
+    // 2. 检查是否过期
+    let expired = stored
+        .expire_time
+        .as_ref()
+        .is_none_or(|expire_time| expire_time.before(&DateTime::now()));
+    if expired {
+        return Err(AppError::InvalidRefreshToken("刷新令牌已过期".to_string()));
+    }
+
+    // 3. 标记为已使用，若标记失败说明令牌已被使用过，视为重放
+    let marked = mark_refresh_token_used(rb, id).await?;
+    if marked.rows_affected == 0 {
+        warn!(
+            "检测到刷新令牌重放, user_id: {}, family_id: {}",
+            user_id, family_id
+        );
+        if let Err(err) = revoke_refresh_token_family(rb, &family_id).await {
+            error!("作废刷新令牌家族失败: {}", err);
+        }
+        return Err(AppError::InvalidRefreshToken("刷新令牌已失效".to_string()));
+    }
+
+    // 4. 查询用户并签发同一家族的新令牌
+    let Some(user) = User::select_by_id(rb, user_id).await? else {
+        return Err(AppError::InvalidRefreshToken("用户不存在".to_string()));
+    };
+    if user.status == Some(USER_STATUS_DISABLED) {
+        return Err(AppError::AccountDisabled);
+    }
+    let username = user.username.unwrap_or_default();
+    issue_token_pair(rb, user_id, &username, &family_id).await
+}
+
+// 签发访问令牌，并在指定家族中保存一个新的刷新令牌
+async fn issue_token_pair(
+    rb: &RBatis,
+    user_id: u64,
+    username: &str,
+    family_id: &str,
+) -> AppResult<TokenPairDto> {
     // 计算token的过期时间
+    let expires_in = CFG.jwt.access_expiration_time * 60;
     let expiration_time = Utc::now()
-        .checked_add_signed(TimeDelta::hours(CFG.jwt.expiration_time))
-        .ok_or("时间计算失败")?
+        .checked_add_signed(TimeDelta::seconds(expires_in))
+        .ok_or_else(|| AppError::Internal("时间计算失败".to_string()))?
         .timestamp() as usize;
 
-    // 获取角色信息
-    match select_roles_by_user_id(rb, user_id).await {
-        Ok(roles) => {
-            // 提取角色名称，过滤掉没有角色名称的项
-            let role_name_vec = roles
-                .iter()
-                .filter_map(|item| item.role_name.clone())
-                .collect();
-
-            // 日志记录生成的token信息
-            debug!("gen_token: {:?}", role_name_vec);
-            // 生成JWT token
-            match generate_jwt_token(user_id, username, expiration_time, &role_name_vec) {
-                Ok(token) => Ok(token),
-                Err(err) => Err(format!("token生成失败：{}", err.to_string())),
-            }
-        }
-        Err(err) => {
-            // 日志记录获取角色信息失败
-            debug!("gen_token_err: {:?}", err);
-            Err(format!("token生成失败，获取角色失败"))
-        }
+    // 先读取权限版本号再读取角色，读取期间角色发生变化时签发的令牌会因版本号过低而失效
+    let auth_version = User::select_by_id(rb, user_id)
+        .await?
+        .and_then(|user| user.auth_version)
+        .unwrap_or_default();
+    // 获取角色信息，提取角色名称，过滤掉没有角色名称的项
+    let role_name_vec: Vec<String> = select_roles_by_user_id(rb, user_id)
+        .await?
+        .iter()
+        .filter_map(|item| item.role_name.clone())
+        .collect();
+
+    // 日志记录生成的token信息
+    debug!("gen_token: {:?}", role_name_vec);
+    // 生成JWT token
+    let access_token = generate_jwt_token(
+        user_id,
+        username,
+        expiration_time,
+        &role_name_vec,
+        auth_version,
+    )
+    .map_err(|err| AppError::Internal(format!("token生成失败：{}", err)))?;
+
+    // 生成并保存刷新令牌
+    let refresh_token = generate_opaque_token();
+    let refresh_expire_time =
+        DateTime::now().add_sub_sec(CFG.jwt.refresh_expiration_time * 60 * 60);
+    let stored = RefreshToken::init_create(
+        user_id,
+        hash_opaque_token(&refresh_token),
+        family_id.to_string(),
+        refresh_expire_time,
+    );
+    let res = RefreshToken::insert(rb, &stored).await?;
+    if res.rows_affected == 0 {
+        return Err(AppError::Internal(
+            "刷新令牌保存失败: 数据写入失败".to_string(),
+        ));
     }
+    Ok(TokenPairDto {
+        access_token,
+        refresh_token,
+        expires_in,
+    })
 }
 
 /// 用户注册函数
@@ -112,176 +499,506 @@ pub async fn gen_token(rb: &RBatis, user_id: u64, username: &str) -> Result<Stri
 /// - register_dto: RegisterDto - 包含用户注册信息的数据传输对象
 ///
 /// 返回:
-/// - Result<User, String> - 注册成功返回用户对象，失败返回错误信息字符串
-pub async fn user_register(rb: &RBatis, register_dto: RegisterDto) -> Result<User, String> {
+/// - AppResult<UserVo> - 注册成功返回不含密码的用户信息，失败返回错误
+pub async fn user_register(rb: &RBatis, register_dto: RegisterDto) -> AppResult<UserVo> {
     // 1. 验证输入
     if register_dto.password != register_dto.recheck_password {
-        return Err("两次输入密码不一致".to_string());
+        return Err(AppError::BadRequest("两次输入密码不一致".to_string()));
     }
     if register_dto.username.is_empty() || register_dto.name.is_empty() {
-        return Err("用户名或姓名不能为空".to_string());
+        return Err(AppError::BadRequest("用户名或姓名不能为空".to_string()));
     }
+    password_policy_service::validate_password(
+        &CFG.password_policy,
+        &CFG.password_hash,
+        "password",
+        &register_dto.username,
+        &register_dto.password,
+    )?;
 
     // 2. 检查用户是否已存在
-    match User::select_by_username(rb, register_dto.username.clone()).await {
-        Ok(Some(_)) => {
-            warn!("用户名已存在");
-            return Err("用户名已存在".to_string());
-        }
-        Ok(None) => {} // 用户名可用
-        Err(err) => {
-            error!("查询用户失败: {}", err);
-            return Err(format!("查询用户失败: {}", err));
-        }
+    if User::select_by_username(rb, register_dto.username.clone())
+        .await?
+        .is_some()
+    {
+        warn!("用户名已存在");
+        return Err(AppError::Conflict("用户名已存在".to_string()));
     }
 
     // 3. 加密密码
-    let encrypted_password = match bcrypt::hash(register_dto.password, DEFAULT_BCRYPT_COST) {
-        Ok(hash) => hash,
-        Err(err) => return Err(format!("加密密码失败: {}", err)),
-    };
+    let encrypted_password = hash_password(&register_dto.password).await?;
 
     // 4. 保存用户信息
     let mut user = User::init_create(register_dto.username, encrypted_password, register_dto.name);
 
-    match User::insert(rb, &user).await {
-        Ok(res) => {
-            if res.rows_affected == 0 {
-                Err("保存用户失败: 数据写入失败".to_string())
-            } else {
-                user.id = Some(res.last_insert_id.as_u64().unwrap());
-                Ok(user)
-            }
-        }
-        Err(err) => Err(format!("用户保存失败: {}", err)),
+    let res = User::insert(rb, &user).await?;
+    if res.rows_affected == 0 {
+        return Err(AppError::Internal("保存用户失败: 数据写入失败".to_string()));
     }
+    user.id = res.last_insert_id.as_u64();
+    Ok(UserVo::from(user))
 }
 
 /// 验证用户令牌和权限
 ///
-/// 该函数首先验证JWT令牌的有效性，然后检查令牌对应的用户是否具有访问指定资源的权限
+/// 该函数首先验证JWT令牌的有效性以及是否已被作废，然后检查令牌对应的用户是否具有访问指定资源的权限
 /// 它用于确保用户不仅拥有有效的身份验证令牌，而且还被授权访问特定的资源或执行特定的操作
 ///
 /// # 参数
 ///
+/// * `rb`: &RBatis - 数据库连接实例，用于回查令牌作废记录
 /// * `token`: &str - 用户的JWT令牌
+/// * `method`: &str - 请求方法
 /// * `path`: &str - 用户试图访问的资源路径
-/// * `role_rel_resource_map`: &DashMap<String, Vec<RoleRelPathDto>> - 角色与资源路径关系的映射
+/// * `permission_matcher`: &RwLock<PermissionMatcher> - 编译好的角色权限匹配器
+/// * `revocation_store`: &TokenRevocationStore - 令牌作废存储
 ///
 /// # 返回值
 ///
-/// * `Result<bool, String>` - 如果用户拥有有效的令牌和适当的权限，则返回Ok(true)，否则返回错误信息
-pub fn verify_token_and_authority(
+/// * `AppResult<TokenClaims>` - 如果用户拥有有效的令牌和适当的权限，则返回令牌声明，否则返回错误
+pub async fn verify_token_and_authority(
+    rb: &RBatis,
     token: &str,
+    method: &str,
     path: &str,
-    role_rel_resource_map: &DashMap<String, Vec<RoleRelPathDto>>,
-) -> Result<bool, String> {
+    permission_matcher: &RwLock<PermissionMatcher>,
+    revocation_store: &TokenRevocationStore,
+) -> AppResult<TokenClaims> {
     // 验证JWT令牌
-    let validate_result = validate_jwt_token(token);
-    match validate_result {
-        Ok(claims) => {
-            // 日志记录验证结果和角色资源映射信息
-            debug!(
-                "auth_service==>verify_token_and_authority=>claims:{:?}, role_rel_resource_map:{:?}",
-                claims, role_rel_resource_map
-            );
-            // 获取令牌的过期时间
-            let expiration_time = claims.exp;
-            // 获取当前时间戳
-            let current_time = Utc::now().timestamp() as usize;
-            // 检查令牌是否已过期
-            if current_time > expiration_time {
-                Err("token已过期".to_string())
-            } else {
-                // 验证资源
-                let roles = claims.roles;
-
-                // 检查用户是否具有访问资源的权限
-                let has_permission =
-                    check_resource_permission(&roles, path, role_rel_resource_map)?;
-                if has_permission {
-                    Ok(true)
-                } else {
-                    Err("权限验证失败".to_string())
-                }
-            }
+    let claims = verify_token(rb, token, revocation_store).await?;
+    // 日志记录验证结果
+    debug!(
+        "auth_service==>verify_token_and_authority=>claims:{:?}, method:{}, path:{}",
+        claims, method, path
+    );
+    // 检查用户是否具有访问资源的权限
+    let super_role = {
+        let matcher = permission_matcher
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        if matcher.is_allowed(&claims.roles, method, path) {
+            return Ok(claims);
         }
-        Err(err) => Err(format!("token验证失败: {}", err)),
+        super_role_of(CFG.auth.super_role.as_deref(), &matcher, &claims.roles)
+    };
+    // 超级管理员跳过资源权限校验，并记录审计日志
+    if let Some(super_role) = super_role {
+        info!(
+            target: AUDIT_LOG_TARGET,
+            "超级管理员越权访问: user_id={}, username={}, role={}, method={}, path={}",
+            claims.user_id, claims.username, super_role, method, path
+        );
+        return Ok(claims);
     }
+    warn!(
+        "用户 {} 的角色 {:?} 均无权访问 {} {}",
+        claims.username, claims.roles, method, path
+    );
+    Err(AppError::Forbidden("权限验证失败".to_string()))
 }
 
-// 检查用户角色是否具有访问指定资源路径的权限
-fn check_resource_permission(
-    roles: &[String],                                             // 用户角色列表
-    path: &str,                                                   // 请求的资源路径
-    role_rel_resource_map: &DashMap<String, Vec<RoleRelPathDto>>, // 角色与资源路径的关系映射
-) -> Result<bool, String> {
-    // 创建一个哈希图用于缓存正则表达式，以提高匹配效率
-    let mut regex_cache = HashMap::new();
-    // 遍历用户的所有角色
-    for role in roles {
-        // 根据角色获取对应的资源路径列表
-        if let Some(role_rel_resource_vec) = role_rel_resource_map.get(role) {
-            // 遍历资源路径列表
-            for rel_path_dto in role_rel_resource_vec.iter() {
-                // 获取资源的匹配值（正则表达式字符串）
-                let resource_value = &rel_path_dto.resource_value;
-                // 在缓存中查找或插入正则表达式
-                let reg = regex_cache
-                    .entry(resource_value.clone())
-                    .or_insert_with(|| {
-                        // 尝试编译正则表达式，如果失败则返回错误信息
-                        Regex::new(resource_value)
-                            .map_err(|e| format!("无效的正则表达式: {}", e))
-                            .unwrap()
-                    });
-                // 如果正则表达式匹配请求路径，则表示用户有访问权限
-                if reg.is_match(path) {
-                    return Ok(true);
+// 用户角色或其继承的祖先角色中包含超级管理员角色时返回该角色，未配置或未拥有时返回None
+fn super_role_of<'a>(
+    super_role: Option<&'a str>,
+    matcher: &PermissionMatcher,
+    roles: &[String],
+) -> Option<&'a str> {
+    super_role.filter(|super_role| {
+        matcher
+            .expand_roles(roles)
+            .iter()
+            .any(|role| role == super_role)
+    })
+}
+
+/// 验证令牌签名、有效期以及是否已被作废
+///
+/// # 参数
+/// * `rb`: &RBatis - 数据库连接实例，用于回查令牌作废记录
+/// * `token`: &str - 用户的JWT令牌
+/// * `revocation_store`: &TokenRevocationStore - 令牌作废存储
+///
+/// # 返回值
+/// * `AppResult<TokenClaims>` - 验证通过时返回令牌声明
+pub async fn verify_token(
+    rb: &RBatis,
+    token: &str,
+    revocation_store: &TokenRevocationStore,
+) -> AppResult<TokenClaims> {
+    // 签名与过期时间由jsonwebtoken校验，过期时单独提示
+    let claims = validate_jwt_token(token).map_err(|err| match err.kind() {
+        ErrorKind::ExpiredSignature => AppError::InvalidToken("token已过期".to_string()),
+        _ => AppError::InvalidToken(format!("token验证失败: {}", err)),
+    })?;
+    // 检查令牌是否已被作废
+    if revocation_store.is_revoked(rb, &claims).await? {
+        return Err(AppError::InvalidToken("token已失效".to_string()));
+    }
+    Ok(claims)
+}
+
+/// 查询当前登录用户的个人信息
+///
+/// # 参数
+/// * `rb`: &RBatis - 数据库连接实例
+/// * `claims`: &TokenClaims - 当前访问令牌的声明，角色取自令牌
+pub async fn get_profile(rb: &RBatis, claims: &TokenClaims) -> AppResult<ProfileVo> {
+    let Some(user) = User::select_by_id(rb, claims.user_id).await? else {
+        return Err(AppError::NotFound("用户不存在".to_string()));
+    };
+    let totp_enabled = totp_service::is_enabled(&user);
+    Ok(ProfileVo {
+        id: claims.user_id,
+        username: user.username.unwrap_or_default(),
+        name: user.name.unwrap_or_default(),
+        roles: claims.roles.clone(),
+        totp_enabled,
+    })
+}
+
+/// 修改当前登录用户的密码
+///
+/// 校验原密码后保存新密码，并作废该用户已签发的全部令牌，
+/// 随后为当前客户端签发新的令牌，使其无需重新登录
+///
+/// # 参数
+/// * `rb`: &RBatis - 数据库连接实例
+/// * `claims`: &TokenClaims - 当前访问令牌的声明
+/// * `dto`: ChangePasswordDto - 原密码与新密码
+/// * `revocation_store`: &TokenRevocationStore - 令牌作废存储
+/// * `login_tracker`: &LoginAttemptTracker - 登录失败计数器，原密码错误计入失败次数
+/// * `ip`: Option<&str> - 客户端IP
+///
+/// # 返回值
+/// * `AppResult<TokenPairDto>` - 新的访问令牌与刷新令牌
+pub async fn change_password(
+    rb: &RBatis,
+    claims: &TokenClaims,
+    dto: ChangePasswordDto,
+    revocation_store: &TokenRevocationStore,
+    login_tracker: &LoginAttemptTracker,
+    ip: Option<&str>,
+) -> AppResult<TokenPairDto> {
+    if dto.new_password != dto.recheck_password {
+        return Err(AppError::BadRequest("两次输入密码不一致".to_string()));
+    }
+    let Some(user) = User::select_by_id(rb, claims.user_id).await? else {
+        return Err(AppError::NotFound("用户不存在".to_string()));
+    };
+    if !check_current_password(login_tracker, &user, &dto.old_password, ip).await? {
+        return Err(AppError::BadRequest("原密码错误".to_string()));
+    }
+    password_policy_service::validate_password(
+        &CFG.password_policy,
+        &CFG.password_hash,
+        "new_password",
+        user.username.as_deref().unwrap_or_default(),
+        &dto.new_password,
+    )?;
+
+    user_service::update_password(rb, claims.user_id, claims.user_id, &dto.new_password).await?;
+    login_tracker.record_success(user.username.as_deref().unwrap_or_default());
+    // 作废其他设备上的令牌，当前令牌可能与作废时间处于同一秒，单独作废
+    revocation_store.revoke_user(rb, claims.user_id).await?;
+    revocation_store.revoke_token(rb, claims).await?;
+    info!("用户 {} 修改了密码", claims.username);
+
+    gen_token(rb, claims.user_id, &claims.username).await
+}
+
+/// 退出登录
+///
+/// 作废当前访问令牌，如果提供了刷新令牌，则同时作废该次登录产生的全部刷新令牌
+///
+/// # 参数
+/// * `rb`: &RBatis - 数据库连接实例
+/// * `claims`: &TokenClaims - 当前访问令牌的声明
+/// * `refresh_token`: Option<&str> - 客户端持有的刷新令牌
+/// * `revocation_store`: &TokenRevocationStore - 令牌作废存储
+pub async fn logout(
+    rb: &RBatis,
+    claims: &TokenClaims,
+    refresh_token: Option<&str>,
+    revocation_store: &TokenRevocationStore,
+) -> AppResult<()> {
+    revocation_store.revoke_token(rb, claims).await?;
+
+    if let Some(refresh_token) = refresh_token {
+        let token_hash = hash_opaque_token(refresh_token);
+        match RefreshToken::select_by_token_hash(rb, &token_hash).await? {
+            // 只允许作废属于当前用户的刷新令牌
+            Some(stored) if stored.user_id == Some(claims.user_id) => {
+                if let Some(family_id) = stored.family_id {
+                    revoke_refresh_token_family(rb, &family_id).await?;
                 }
             }
+            _ => warn!("退出登录时提供的刷新令牌无效, user_id: {}", claims.user_id),
         }
     }
-    // 如果没有找到匹配的资源路径，表示用户没有访问权限
-    Ok(false)
+    Ok(())
 }
 
-/// 异步加载所有角色关联资源信息
+/// 退出全部登录
 ///
-/// 本函数通过数据库操作获取所有角色关联资源信息，并将其组织成哈希表形式，便于根据角色名快速查询关联资源
+/// 作废该用户已签发的全部访问令牌与刷新令牌
+///
+/// # 参数
+/// * `rb`: &RBatis - 数据库连接实例
+/// * `claims`: &TokenClaims - 当前访问令牌的声明
+/// * `revocation_store`: &TokenRevocationStore - 令牌作废存储
+pub async fn logout_all(
+    rb: &RBatis,
+    claims: &TokenClaims,
+    revocation_store: &TokenRevocationStore,
+) -> AppResult<()> {
+    revocation_store.revoke_user(rb, claims.user_id).await?;
+    // 当前令牌可能与作废时间处于同一秒，单独作废
+    revocation_store.revoke_token(rb, claims).await
+}
+
+/// 异步加载所有角色关联资源信息，并编译为权限匹配器
+///
+/// 每个角色的路径规则只在加载时编译一次，无效的正则会被剔除并记录在匹配器中；
+/// 角色继承关系同时被展开，循环继承会被记录而不会导致死循环
 ///
 /// # 参数
 /// * `rb`: &RBatis - 数据库连接实例引用，用于执行数据库操作
 ///
 /// # 返回
-/// 返回一个哈希表，其中键为角色名，值为与该角色关联的资源路径列表如果数据库中没有找到任何角色关联信息，将返回一个空的哈希表
-///
-/// # 错误处理
-/// 如果数据库操作失败，将打印错误日志，并返回一个空的哈希表根据业务需求，也可以选择返回错误或执行其他错误处理策略
-pub async fn load_all_role_rel_resource(rb: &RBatis) -> DashMap<String, Vec<RoleRelPathDto>> {
-    // 尝试从数据库中选择所有角色关联资源路径信息
-    match select_all_role_rel_path(rb).await {
-        Ok(role_rel_path) => {
-            // 如果查询结果为空，则记录日志并返回一个空的哈希表
-            if role_rel_path.is_empty() {
-                info!("未找到角色资源相关数据");
-                return DashMap::new();
-            }
+/// 返回编译好的权限匹配器，数据库操作失败时返回错误
+pub async fn load_all_role_rel_resource(rb: &RBatis) -> AppResult<PermissionMatcher> {
+    // 先读取版本号再读取规则，加载期间发生的修改会在下次轮询时被发现
+    let version = current_permission_version(rb).await?;
+    // 从数据库中选择所有角色关联资源路径信息
+    let role_rel_path = select_all_role_rel_path(rb).await?;
+    // 如果查询结果为空，则记录日志
+    if role_rel_path.is_empty() {
+        info!("未找到角色资源相关数据");
+    }
+    // 角色继承关系在构建时展开，存在循环时记录在匹配器中
+    let role_inherit = select_all_role_inherit(rb).await?;
+    Ok(PermissionMatcher::build(role_rel_path, &role_inherit).with_version(version))
+}
 
-            // 初始化一个空的哈希表来存储角色名与关联资源路径的映射
-            let map: DashMap<String, Vec<RoleRelPathDto>> = DashMap::new();
-            // 遍历查询结果，将每个角色关联的资源路径添加到哈希表中对应的键下
-            for item in role_rel_path {
-                let key = item.role_name.clone();
-                map.entry(key).or_default().push(item);
-            }
-            // 返回填充好的哈希表
-            map
-        }
-        Err(e) => {
-            // 如果数据库操作失败，记录错误日志，并根据业务需求决定是否返回空哈希表或传播错误
-            error!("角色资源关联数据加载失败: {}", e);
-            DashMap::new()
-        }
+/// 递增权限规则版本号，然后重新加载角色关联资源并整体替换权限匹配器
+///
+/// 权限数据修改后调用，其他实例轮询到版本号变化后也会重新加载。数据库读取失败时保留原有规则不变
+///
+/// # 参数
+/// * `rb`: &RBatis - 数据库连接实例引用
+/// * `permission_matcher`: &RwLock<PermissionMatcher> - 应用状态中的权限匹配器
+///
+/// # 返回
+/// 返回重新加载后生效的规则以及被剔除的无效规则
+pub async fn reload_permission_matcher(
+    rb: &RBatis,
+    permission_matcher: &RwLock<PermissionMatcher>,
+) -> AppResult<PermissionSnapshotDto> {
+    bump_permission_version(rb).await?;
+    replace_permission_matcher(rb, permission_matcher).await
+}
+
+/// 检查数据库中的权限规则版本号，与已加载的版本不一致时重新加载
+///
+/// 由后台任务定时调用，使多个实例的权限规则保持一致
+///
+/// # 参数
+/// * `rb`: &RBatis - 数据库连接实例引用
+/// * `permission_matcher`: &RwLock<PermissionMatcher> - 应用状态中的权限匹配器
+///
+/// # 返回
+/// 返回是否重新加载了权限规则
+pub async fn sync_permission_matcher(
+    rb: &RBatis,
+    permission_matcher: &RwLock<PermissionMatcher>,
+) -> AppResult<bool> {
+    let current_version = current_permission_version(rb).await?;
+    let loaded_version = permission_matcher
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .version();
+    if current_version == loaded_version {
+        return Ok(false);
+    }
+    info!(
+        "权限规则版本号由 {} 变为 {}，重新加载",
+        loaded_version, current_version
+    );
+    replace_permission_matcher(rb, permission_matcher).await?;
+    Ok(true)
+}
+
+/// 查询已加载的权限规则版本号与数据库中的最新版本号，用于确认各实例是否已同步
+pub async fn get_permission_version(
+    rb: &RBatis,
+    permission_matcher: &RwLock<PermissionMatcher>,
+) -> AppResult<PermissionVersionDto> {
+    let current_version = current_permission_version(rb).await?;
+    let loaded_version = permission_matcher
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .version();
+    Ok(PermissionVersionDto {
+        loaded_version,
+        current_version,
+    })
+}
+
+// 加载权限规则并替换匹配器，并发加载时不会用较旧版本的规则覆盖较新版本
+async fn replace_permission_matcher(
+    rb: &RBatis,
+    permission_matcher: &RwLock<PermissionMatcher>,
+) -> AppResult<PermissionSnapshotDto> {
+    let matcher = load_all_role_rel_resource(rb).await?;
+    let snapshot = matcher.snapshot();
+    info!(
+        "重新加载角色资源: 版本 {}, {} 个角色, {} 条无效规则, {} 个循环继承",
+        snapshot.version,
+        snapshot.role_rel_resource.len(),
+        snapshot.invalid_patterns.len(),
+        snapshot.role_cycles.len()
+    );
+    let mut current = permission_matcher
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+    if matcher.version() >= current.version() {
+        *current = matcher;
+    }
+    Ok(snapshot)
+}
+
+// 数据库中的权限规则版本号，记录不存在时为0
+async fn current_permission_version(rb: &RBatis) -> AppResult<u64> {
+    Ok(PermissionVersion::select_current(rb)
+        .await?
+        .and_then(|item| item.version)
+        .unwrap_or_default())
+}
+
+/// 解释指定用户以指定方法访问指定路径时的权限判定过程，用于排查403问题
+///
+/// 角色取自数据库中用户当前拥有的角色，而请求校验使用的是令牌中的角色，
+/// 两者不一致时用户需要重新获取令牌
+///
+/// # 参数
+/// * `rb`: &RBatis - 数据库连接实例
+/// * `query`: &PermissionExplainQueryDto - 用户ID、请求方法与路径
+/// * `permission_matcher`: &RwLock<PermissionMatcher> - 应用状态中的权限匹配器
+///
+/// # 返回
+/// 返回用户角色、逐条评估的规则、命中的规则以及最终判定
+pub async fn explain_permission(
+    rb: &RBatis,
+    query: &PermissionExplainQueryDto,
+    permission_matcher: &RwLock<PermissionMatcher>,
+) -> AppResult<PermissionExplainDto> {
+    let user = User::select_by_id(rb, query.user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("用户不存在".to_string()))?;
+    let roles: Vec<String> = select_roles_by_user_id(rb, query.user_id)
+        .await?
+        .into_iter()
+        .filter_map(|role| role.role_name)
+        .collect();
+    let method = query
+        .method
+        .as_deref()
+        .map(str::trim)
+        .filter(|method| !method.is_empty())
+        .unwrap_or("GET")
+        .to_uppercase();
+
+    let (expanded_roles, evaluations, super_role) = {
+        let matcher = permission_matcher
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        (
+            matcher.expand_roles(&roles),
+            matcher.explain(&roles, &method, &query.path),
+            super_role_of(CFG.auth.super_role.as_deref(), &matcher, &roles),
+        )
+    };
+    let matched = evaluations
+        .iter()
+        .find(|item| item.path_matched && item.method_matched)
+        .cloned();
+
+    let decision = match ACCESS_RULES.access_level(&method, &query.path) {
+        AccessLevel::Public => PermissionDecision::Public,
+        _ if user.status == Some(USER_STATUS_DISABLED) => PermissionDecision::AccountDisabled,
+        AccessLevel::Authenticated => PermissionDecision::Authenticated,
+        AccessLevel::Authorized if matched.is_some() => PermissionDecision::Granted,
+        AccessLevel::Authorized if super_role.is_some() => PermissionDecision::SuperRole,
+        AccessLevel::Authorized => PermissionDecision::Denied,
+    };
+    Ok(PermissionExplainDto {
+        user_id: query.user_id,
+        username: user.username,
+        method,
+        path: query.path.clone(),
+        roles,
+        expanded_roles,
+        evaluations,
+        matched,
+        allowed: !matches!(
+            decision,
+            PermissionDecision::AccountDisabled | PermissionDecision::Denied
+        ),
+        decision,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dto::auth_dto::RoleInheritDto;
+
+    #[test]
+    fn test_login_failure_audit() {
+        // 用户不存在与密码错误都是同一个原因
+        assert_eq!(
+            "invalid_credentials",
+            LoginFailureReason::from(&AppError::InvalidCredentials).as_str()
+        );
+        assert_eq!(
+            "locked",
+            LoginFailureReason::from(&AppError::AccountLocked(60)).as_str()
+        );
+        assert_eq!(
+            "invalid_otp",
+            LoginFailureReason::from(&AppError::InvalidOtp).as_str()
+        );
+        assert_eq!(
+            username_fingerprint("Alice"),
+            username_fingerprint(" alice")
+        );
+        assert_ne!(username_fingerprint("alice"), username_fingerprint("bob"));
+        assert!(!PASSWORD_HASHER.verify("", &DUMMY_PASSWORD_HASH).unwrap());
+    }
+
+    #[test]
+    fn test_inherited_super_role() {
+        // ops 继承 admin，同样获得超级管理员的越权访问
+        let matcher = PermissionMatcher::build(
+            vec![],
+            &[RoleInheritDto {
+                role_name: "ops".to_string(),
+                parent_role_name: "admin".to_string(),
+            }],
+        );
+        let roles = |role: &str| vec![role.to_string()];
+        assert_eq!(
+            Some("admin"),
+            super_role_of(Some("admin"), &matcher, &roles("admin"))
+        );
+        assert_eq!(
+            Some("admin"),
+            super_role_of(Some("admin"), &matcher, &roles("ops"))
+        );
+        assert_eq!(
+            None,
+            super_role_of(Some("admin"), &matcher, &roles("staff"))
+        );
+        assert_eq!(None, super_role_of(None, &matcher, &roles("admin")));
     }
 }
@@ -0,0 +1,213 @@
+use crate::model::config::LoginThrottle;
+use crate::model::error::{AppError, AppResult};
+use chrono::Utc;
+use dashmap::DashMap;
+use log::warn;
+
+/// 登录失败计数器
+///
+/// 按用户名与客户端IP分别统计连续登录失败次数，达到阈值后锁定一段时间，
+/// 锁定期间直接拒绝登录，不再校验密码。再次被锁定时锁定时长翻倍，直到配置的上限。
+/// 计数保存在内存中，多实例部署时各实例分别统计
+#[derive(Debug, Default)]
+pub struct LoginAttemptTracker {
+    config: LoginThrottle,
+    // 统计键 -> 失败记录，统计键为 user:<用户名> 或 ip:<客户端IP>
+    attempts: DashMap<String, Attempt>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Attempt {
+    // 当前窗口内连续失败的次数
+    failures: u32,
+    // 最近一次失败的时间戳
+    last_failure: i64,
+    // 已被锁定的次数，用于计算下一次的锁定时长
+    locks: u32,
+    // 锁定截止时间戳
+    locked_until: i64,
+}
+
+impl LoginAttemptTracker {
+    pub fn new(config: LoginThrottle) -> Self {
+        LoginAttemptTracker {
+            config,
+            attempts: DashMap::new(),
+        }
+    }
+
+    /// 校验用户名与客户端IP是否处于锁定中，锁定时返回剩余锁定秒数
+    ///
+    /// # 参数
+    /// * `username` - 登录的用户名
+    /// * `ip` - 客户端IP，无法获取时只按用户名限制
+    pub fn check(&self, username: &str, ip: Option<&str>) -> AppResult<()> {
+        self.check_at(username, ip, Utc::now().timestamp())
+    }
+
+    /// 记录一次登录失败，达到阈值时锁定
+    pub fn record_failure(&self, username: &str, ip: Option<&str>) {
+        self.record_failure_at(username, ip, Utc::now().timestamp());
+    }
+
+    /// 登录成功后清除该用户名的失败记录
+    ///
+    /// 客户端IP的记录不清除，避免攻击者用自己的账号登录来重置IP的失败次数
+    pub fn record_success(&self, username: &str) {
+        if self.config.enabled {
+            self.attempts.remove(&user_key(username));
+        }
+    }
+
+    /// 清理已过期的失败记录
+    pub fn purge_expired(&self) {
+        let now = Utc::now().timestamp();
+        self.attempts
+            .retain(|_, attempt| !self.is_expired(attempt, now));
+    }
+
+    fn check_at(&self, username: &str, ip: Option<&str>, now: i64) -> AppResult<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        let retry_after = self
+            .keys(username, ip)
+            .iter()
+            .filter_map(|(key, _)| self.attempts.get(key).map(|item| item.locked_until - now))
+            .max()
+            .unwrap_or_default();
+        if retry_after > 0 {
+            return Err(AppError::AccountLocked(retry_after));
+        }
+        Ok(())
+    }
+
+    fn record_failure_at(&self, username: &str, ip: Option<&str>, now: i64) {
+        if !self.config.enabled {
+            return;
+        }
+        for (key, max_failures) in self.keys(username, ip) {
+            let mut attempt = self.attempts.entry(key.clone()).or_default();
+            if self.is_expired(&attempt, now) {
+                *attempt = Attempt::default();
+            } else if now - attempt.last_failure > self.config.failure_window_second {
+                attempt.failures = 0;
+            }
+            attempt.failures += 1;
+            attempt.last_failure = now;
+            if attempt.failures >= max_failures {
+                let lock_second = self.lock_second(attempt.locks);
+                attempt.failures = 0;
+                attempt.locks += 1;
+                attempt.locked_until = now + lock_second;
+                warn!("{} 登录失败次数过多，锁定 {} 秒", key, lock_second);
+            }
+        }
+    }
+
+    // 第 locks + 1 次锁定的时长
+    fn lock_second(&self, locks: u32) -> i64 {
+        2_i64
+            .checked_pow(locks)
+            .and_then(|factor| self.config.base_lock_second.checked_mul(factor))
+            .unwrap_or(i64::MAX)
+            .min(self.config.max_lock_second)
+    }
+
+    // 锁定已结束且距上次失败与锁定结束都超过统计窗口的记录不再有意义
+    fn is_expired(&self, attempt: &Attempt, now: i64) -> bool {
+        now - attempt.last_failure.max(attempt.locked_until) > self.config.failure_window_second
+    }
+
+    fn keys(&self, username: &str, ip: Option<&str>) -> Vec<(String, u32)> {
+        let mut keys = vec![(user_key(username), self.config.max_failures_per_user)];
+        if let Some(ip) = ip {
+            keys.push((format!("ip:{}", ip), self.config.max_failures_per_ip));
+        }
+        keys
+    }
+}
+
+// 用户名不区分大小写，与数据库的默认排序规则保持一致
+fn user_key(username: &str) -> String {
+    format!("user:{}", username.trim().to_lowercase())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tracker() -> LoginAttemptTracker {
+        LoginAttemptTracker::new(LoginThrottle {
+            max_failures_per_user: 3,
+            max_failures_per_ip: 5,
+            failure_window_second: 600,
+            base_lock_second: 60,
+            max_lock_second: 200,
+            ..Default::default()
+        })
+    }
+
+    fn retry_after(result: AppResult<()>) -> i64 {
+        match result {
+            Err(AppError::AccountLocked(retry_after)) => retry_after,
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn test_user_lockout_backoff() {
+        let tracker = tracker();
+        for _ in 0..2 {
+            tracker.record_failure_at("Alice", None, 0);
+        }
+        assert!(tracker.check_at("alice", None, 0).is_ok());
+
+        // 第3次失败锁定60秒，之后依次为120秒、上限200秒
+        tracker.record_failure_at("alice", None, 0);
+        assert_eq!(60, retry_after(tracker.check_at("ALICE", None, 0)));
+        assert!(tracker.check_at("alice", None, 60).is_ok());
+
+        for _ in 0..3 {
+            tracker.record_failure_at("alice", None, 60);
+        }
+        assert_eq!(120, retry_after(tracker.check_at("alice", None, 60)));
+
+        for _ in 0..3 {
+            tracker.record_failure_at("alice", None, 180);
+        }
+        assert_eq!(200, retry_after(tracker.check_at("alice", None, 180)));
+
+        // 登录成功后清除用户名的记录
+        tracker.record_success("alice");
+        assert!(tracker.check_at("alice", None, 180).is_ok());
+    }
+
+    #[test]
+    fn test_failure_window() {
+        let tracker = tracker();
+        tracker.record_failure_at("bob", None, 0);
+        tracker.record_failure_at("bob", None, 0);
+        // 超过统计窗口后重新计数
+        tracker.record_failure_at("bob", None, 601);
+        assert!(tracker.check_at("bob", None, 601).is_ok());
+    }
+
+    #[test]
+    fn test_ip_lockout() {
+        let tracker = tracker();
+        // 同一IP尝试不同用户名，每个用户名都未达到阈值
+        for index in 0..5 {
+            tracker.record_failure_at(&format!("user{}", index), Some("10.0.0.1"), 0);
+        }
+        assert_eq!(
+            60,
+            retry_after(tracker.check_at("other", Some("10.0.0.1"), 0))
+        );
+        assert!(tracker.check_at("other", Some("10.0.0.2"), 0).is_ok());
+
+        // 登录成功不会清除IP的记录
+        tracker.record_success("other");
+        assert!(tracker.check_at("other", Some("10.0.0.1"), 0).is_err());
+    }
+}
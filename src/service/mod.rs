@@ -1 +1,9 @@
 pub mod auth_service;
+pub mod login_throttle_service;
+pub mod password_policy_service;
+pub mod permission_service;
+pub mod resource_service;
+pub mod role_service;
+pub mod token_revocation_service;
+pub mod totp_service;
+pub mod user_service;
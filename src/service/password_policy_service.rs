@@ -0,0 +1,221 @@
+use crate::model::{
+    config::{PasswordHashAlgorithm, PasswordHashing, PasswordPolicy},
+    error::{AppError, AppResult, FieldError},
+};
+use lazy_static::lazy_static;
+use std::collections::HashSet;
+
+// 用户名短于该长度时不检查密码是否包含用户名，避免误伤
+const MIN_USERNAME_CHECK_LENGTH: usize = 3;
+
+// bcrypt只使用密码的前72个字节，超出部分被忽略
+const BCRYPT_MAX_PASSWORD_BYTES: usize = 72;
+
+lazy_static! {
+    // 内置的常见弱密码列表，统一转为小写
+    static ref COMMON_PASSWORDS: HashSet<String> = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/resources/common_passwords.txt"
+    ))
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    .map(str::to_lowercase)
+    .collect();
+}
+
+/// 按密码策略校验密码
+///
+/// 所有设置密码的地方都应调用该函数，不满足的规则会全部返回，而不是只返回第一条。
+/// 使用bcrypt时密码还不能超过72字节，非ASCII字符按UTF-8编码的字节数计算
+///
+/// # 参数
+/// * `policy` - 密码策略
+/// * `hashing` - 密码哈希配置，用于确定算法允许的最大字节数
+/// * `field` - 请求体中密码的字段名，用于字段错误
+/// * `username` - 密码所属用户的用户名
+/// * `password` - 明文密码
+///
+/// # 返回
+/// 不满足策略时返回`AppError::Validation`
+pub fn validate_password(
+    policy: &PasswordPolicy,
+    hashing: &PasswordHashing,
+    field: &str,
+    username: &str,
+    password: &str,
+) -> AppResult<()> {
+    let max_bytes = match hashing.algorithm {
+        PasswordHashAlgorithm::Bcrypt => Some(BCRYPT_MAX_PASSWORD_BYTES),
+        PasswordHashAlgorithm::Argon2id => None,
+    };
+    let errors = check_password(policy, max_bytes, field, username, password);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::Validation(errors))
+    }
+}
+
+fn check_password(
+    policy: &PasswordPolicy,
+    max_bytes: Option<usize>,
+    field: &str,
+    username: &str,
+    password: &str,
+) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    // 无论如何配置都不允许空密码
+    let min_length = policy.min_length.max(1);
+    let length = password.chars().count();
+    if length < min_length {
+        errors.push(FieldError::new(
+            field,
+            "too_short",
+            format!("密码长度不能少于{}位", min_length),
+        ));
+    }
+    if length > policy.max_length {
+        errors.push(FieldError::new(
+            field,
+            "too_long",
+            format!("密码长度不能超过{}位", policy.max_length),
+        ));
+    } else if let Some(max_bytes) = max_bytes.filter(|max_bytes| password.len() > *max_bytes) {
+        errors.push(FieldError::new(
+            field,
+            "too_long",
+            format!("密码长度不能超过{}字节，中文等字符占多个字节", max_bytes),
+        ));
+    }
+    if character_classes(password) < policy.min_character_classes {
+        errors.push(FieldError::new(
+            field,
+            "character_classes",
+            format!(
+                "密码需至少包含小写字母、大写字母、数字、符号中的{}类",
+                policy.min_character_classes
+            ),
+        ));
+    }
+    let lower_password = password.to_lowercase();
+    if policy.deny_common_passwords && COMMON_PASSWORDS.contains(&lower_password) {
+        errors.push(FieldError::new(
+            field,
+            "common_password",
+            "密码过于常见".to_string(),
+        ));
+    }
+    let lower_username = username.trim().to_lowercase();
+    if policy.deny_username
+        && lower_username.chars().count() >= MIN_USERNAME_CHECK_LENGTH
+        && lower_password.contains(&lower_username)
+    {
+        errors.push(FieldError::new(
+            field,
+            "contains_username",
+            "密码不能包含用户名".to_string(),
+        ));
+    }
+    errors
+}
+
+// 密码包含的字符类别数：小写字母、大写字母、数字、其他符号
+fn character_classes(password: &str) -> usize {
+    let mut classes = [false; 4];
+    for c in password.chars() {
+        let index = if c.is_lowercase() {
+            0
+        } else if c.is_uppercase() {
+            1
+        } else if c.is_ascii_digit() {
+            2
+        } else {
+            3
+        };
+        classes[index] = true;
+    }
+    classes.iter().filter(|present| **present).count()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hashing(algorithm: PasswordHashAlgorithm) -> PasswordHashing {
+        PasswordHashing {
+            algorithm,
+            ..Default::default()
+        }
+    }
+
+    fn validate(
+        policy: &PasswordPolicy,
+        algorithm: PasswordHashAlgorithm,
+        username: &str,
+        password: &str,
+    ) -> Vec<String> {
+        match validate_password(policy, &hashing(algorithm), "password", username, password) {
+            Ok(()) => vec![],
+            Err(AppError::Validation(errors)) => {
+                assert!(errors.iter().all(|error| error.field == "password"));
+                errors.into_iter().map(|error| error.code).collect()
+            }
+            Err(err) => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    fn codes(username: &str, password: &str) -> Vec<String> {
+        validate(
+            &PasswordPolicy::default(),
+            PasswordHashAlgorithm::Bcrypt,
+            username,
+            password,
+        )
+    }
+
+    #[test]
+    fn test_password_policy() {
+        assert!(codes("alice", "Correct-Horse-7").is_empty());
+        assert_eq!(vec!["too_short", "character_classes"], codes("alice", ""));
+        assert_eq!(vec!["character_classes"], codes("alice", "abcdefghij"));
+        assert_eq!(vec!["common_password"], codes("alice", "PASSWORD123"));
+        assert_eq!(vec!["contains_username"], codes("Alice", "my-alice-2024"));
+        // 过短的用户名不检查
+        assert!(codes("al", "my-al-pass-2024").is_empty());
+        assert_eq!(vec!["too_long"], codes("alice", &"a1".repeat(40)));
+    }
+
+    #[test]
+    fn test_bcrypt_byte_limit() {
+        // 字符数未超限，但超过bcrypt的72字节；Argon2id不限制字节数
+        let password = "密码Ab1".repeat(10);
+        assert_eq!(vec!["too_long"], codes("alice", &password));
+        assert!(
+            validate(
+                &PasswordPolicy::default(),
+                PasswordHashAlgorithm::Argon2id,
+                "alice",
+                &password
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_policy_disabled_rules() {
+        let policy = PasswordPolicy {
+            min_length: 0,
+            min_character_classes: 0,
+            deny_common_passwords: false,
+            deny_username: false,
+            ..Default::default()
+        };
+        // 关闭全部规则后仍不允许空密码
+        assert_eq!(
+            vec!["too_short"],
+            validate(&policy, PasswordHashAlgorithm::Argon2id, "alice", "")
+        );
+        assert!(validate(&policy, PasswordHashAlgorithm::Argon2id, "alice", "alice").is_empty());
+    }
+}
@@ -0,0 +1,587 @@
+use crate::dto::auth_dto::{
+    InvalidPatternDto, PatternEvaluationDto, PermissionSnapshotDto, RoleInheritDto, RoleRelPathDto,
+};
+use crate::model::config::{Auth, PathRule};
+use crate::util::config_util::CFG;
+use lazy_static::lazy_static;
+use log::error;
+use regex::{Regex, RegexSet};
+use std::collections::{BTreeSet, HashMap};
+
+// 内置的公开路径：(请求方法, 路径)
+const BUILTIN_PUBLIC_PATHS: [(&str, &str); 4] = [
+    ("POST", "/auth/login"),
+    ("POST", "/auth/login/totp"),
+    ("POST", "/auth/register"),
+    ("POST", "/auth/refresh"),
+];
+
+// 内置的仅需登录的路径：(请求方法, 路径)，请求方法为空表示全部方法
+const BUILTIN_AUTHENTICATED_PATHS: [(&str, &str); 4] = [
+    ("", "/auth/me"),
+    ("", "/auth/me/**"),
+    ("POST", "/auth/logout"),
+    ("POST", "/auth/logout_all"),
+];
+
+/// 权限匹配器
+///
+/// 在加载角色资源时一次性把每个角色的路径正则编译为`RegexSet`，请求时只做匹配不再编译。
+/// 无效的正则在加载阶段被剔除并记录，不会影响其他规则，也不会在请求中panic。
+/// 角色继承关系同样在加载时展开，匹配时角色拥有自身及全部祖先角色的规则
+#[derive(Debug, Default)]
+pub struct PermissionMatcher {
+    // 角色名 -> 该角色编译后的路径规则
+    roles: HashMap<String, RolePermission>,
+    // 加载时被剔除的无效规则
+    invalid_patterns: Vec<InvalidPatternDto>,
+    // 角色名 -> 继承的全部祖先角色
+    ancestors: HashMap<String, Vec<String>>,
+    // 检测到的循环继承
+    cycles: Vec<Vec<String>>,
+    // 加载规则时数据库中的权限版本号
+    version: u64,
+}
+
+#[derive(Debug)]
+struct RolePermission {
+    // 路径正则集合，下标与rules一一对应
+    paths: RegexSet,
+    rules: Vec<RoleRelPathDto>,
+}
+
+impl PermissionMatcher {
+    /// 根据角色资源关系构建权限匹配器
+    ///
+    /// # 参数
+    /// * `list` - 角色与资源路径的关系列表
+    /// * `inherits` - 角色与父角色的继承关系列表
+    pub fn build(list: Vec<RoleRelPathDto>, inherits: &[RoleInheritDto]) -> Self {
+        let mut invalid_patterns = Vec::new();
+        let mut grouped: HashMap<String, Vec<RoleRelPathDto>> = HashMap::new();
+        for item in list {
+            // 逐条校验，避免一条无效规则导致整个角色的规则失效
+            match Regex::new(&item.resource_value) {
+                Ok(_) => grouped
+                    .entry(item.role_name.clone())
+                    .or_default()
+                    .push(item),
+                Err(err) => {
+                    error!(
+                        "角色 {} 的资源规则 {} 不是有效的正则表达式: {}",
+                        item.role_name, item.resource_value, err
+                    );
+                    invalid_patterns.push(InvalidPatternDto {
+                        role_name: item.role_name,
+                        resource_value: item.resource_value,
+                        error: err.to_string(),
+                    });
+                }
+            }
+        }
+
+        let mut roles = HashMap::new();
+        for (role_name, rules) in grouped {
+            match RegexSet::new(rules.iter().map(|rule| &rule.resource_value)) {
+                Ok(paths) => {
+                    roles.insert(role_name, RolePermission { paths, rules });
+                }
+                // 单条规则有效但合并后超出大小限制等情况，整个角色的规则都不生效
+                Err(err) => {
+                    error!("角色 {} 的资源规则编译失败: {}", role_name, err);
+                    invalid_patterns.extend(rules.into_iter().map(|rule| InvalidPatternDto {
+                        role_name: rule.role_name,
+                        resource_value: rule.resource_value,
+                        error: err.to_string(),
+                    }));
+                }
+            }
+        }
+
+        let (ancestors, cycles) = flatten_role_inherit(inherits);
+        for cycle in &cycles {
+            error!("角色存在循环继承: {}", cycle.join(" -> "));
+        }
+
+        PermissionMatcher {
+            roles,
+            invalid_patterns,
+            ancestors,
+            cycles,
+            version: 0,
+        }
+    }
+
+    /// 设置规则对应的权限版本号
+    pub fn with_version(mut self, version: u64) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// 规则对应的权限版本号
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// 展开角色列表，返回其中的角色及其全部祖先角色，结果不含重复项
+    pub fn expand_roles(&self, roles: &[String]) -> Vec<String> {
+        let mut expanded: Vec<String> = Vec::new();
+        for role in roles {
+            let ancestors = self.ancestors.get(role).into_iter().flatten();
+            for item in std::iter::once(role).chain(ancestors) {
+                if !expanded.contains(item) {
+                    expanded.push(item.clone());
+                }
+            }
+        }
+        expanded
+    }
+
+    /// 检查角色列表中是否有角色允许以指定方法访问指定路径
+    ///
+    /// # 参数
+    /// * `roles` - 用户角色列表
+    /// * `method` - 请求方法
+    /// * `path` - 请求路径
+    pub fn is_allowed(&self, roles: &[String], method: &str, path: &str) -> bool {
+        self.expand_roles(roles)
+            .iter()
+            .filter_map(|role| self.roles.get(role))
+            .any(|permission| {
+                permission.paths.matches(path).iter().any(|index| {
+                    method_matches(permission.rules[index].resource_method.as_deref(), method)
+                })
+            })
+    }
+
+    /// 逐条评估角色列表（含继承的角色）的资源规则，用于排查权限问题
+    ///
+    /// # 参数
+    /// * `roles` - 用户角色列表
+    /// * `method` - 请求方法
+    /// * `path` - 请求路径
+    pub fn explain(&self, roles: &[String], method: &str, path: &str) -> Vec<PatternEvaluationDto> {
+        let mut evaluations = Vec::new();
+        for role in self.expand_roles(roles) {
+            let Some(permission) = self.roles.get(&role) else {
+                continue;
+            };
+            let matches = permission.paths.matches(path);
+            for (index, rule) in permission.rules.iter().enumerate() {
+                evaluations.push(PatternEvaluationDto {
+                    role_name: role.clone(),
+                    resource_value: rule.resource_value.clone(),
+                    resource_method: rule.resource_method.clone(),
+                    path_matched: matches.matched(index),
+                    method_matched: method_matches(rule.resource_method.as_deref(), method),
+                });
+            }
+        }
+        evaluations
+    }
+
+    /// 当前生效的规则与加载时被剔除的无效规则
+    pub fn snapshot(&self) -> PermissionSnapshotDto {
+        PermissionSnapshotDto {
+            role_rel_resource: self
+                .roles
+                .iter()
+                .map(|(role_name, permission)| (role_name.clone(), permission.rules.clone()))
+                .collect(),
+            invalid_patterns: self.invalid_patterns.clone(),
+            role_inherit: self.ancestors.clone(),
+            role_cycles: self.cycles.clone(),
+            version: self.version,
+        }
+    }
+}
+
+/// 展开角色继承关系
+///
+/// 返回每个角色继承的全部祖先角色（不含自身，按就近顺序），以及检测到的循环继承。
+/// 遇到循环时不再沿环继续展开，环上的角色仍然继承环外的祖先
+///
+/// # 参数
+/// * `inherits` - 角色与父角色的继承关系列表
+pub fn flatten_role_inherit(
+    inherits: &[RoleInheritDto],
+) -> (HashMap<String, Vec<String>>, Vec<Vec<String>>) {
+    let mut parents: HashMap<&str, Vec<&str>> = HashMap::new();
+    for item in inherits {
+        parents
+            .entry(&item.role_name)
+            .or_default()
+            .push(&item.parent_role_name);
+    }
+
+    let mut ancestors = HashMap::new();
+    let mut cycles = BTreeSet::new();
+    for role in parents.keys() {
+        let mut collected = Vec::new();
+        collect_ancestors(&parents, &mut vec![role], &mut collected, &mut cycles);
+        ancestors.insert(role.to_string(), collected);
+    }
+    (ancestors, cycles.into_iter().collect())
+}
+
+// 深度优先收集祖先角色，path为当前的继承路径，用于发现循环
+fn collect_ancestors<'a>(
+    parents: &HashMap<&'a str, Vec<&'a str>>,
+    path: &mut Vec<&'a str>,
+    collected: &mut Vec<String>,
+    cycles: &mut BTreeSet<Vec<String>>,
+) {
+    let Some(role) = path.last().copied() else {
+        return;
+    };
+    for parent in parents.get(role).into_iter().flatten() {
+        if let Some(start) = path.iter().position(|item| item == parent) {
+            cycles.insert(normalize_cycle(&path[start..]));
+            continue;
+        }
+        if collected.iter().any(|item| item == parent) {
+            continue;
+        }
+        collected.push(parent.to_string());
+        path.push(parent);
+        collect_ancestors(parents, path, collected, cycles);
+        path.pop();
+    }
+}
+
+// 把环旋转为从名称最小的角色开始，使同一个环只被记录一次
+fn normalize_cycle(cycle: &[&str]) -> Vec<String> {
+    let start = (0..cycle.len())
+        .min_by_key(|index| cycle[*index])
+        .unwrap_or_default();
+    cycle[start..]
+        .iter()
+        .chain(&cycle[..start])
+        .map(|role| role.to_string())
+        .collect()
+}
+
+/// 认证中间件对请求的处理级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessLevel {
+    // 无需令牌
+    Public,
+    // 只需有效令牌，不校验资源权限
+    Authenticated,
+    // 需要有效令牌并通过资源权限校验
+    Authorized,
+}
+
+/// 认证中间件的路径规则，由内置规则与`[auth]`配置合并而成
+#[derive(Debug)]
+pub struct AccessRules {
+    public: PathRuleMatcher,
+    authenticated: PathRuleMatcher,
+}
+
+impl AccessRules {
+    /// 根据配置构建路径规则，规则无效时返回错误
+    pub fn from_config(auth: &Auth) -> Result<Self, String> {
+        Ok(AccessRules {
+            public: PathRuleMatcher::build(&with_builtin(
+                &BUILTIN_PUBLIC_PATHS,
+                &auth.public_paths,
+            ))?,
+            authenticated: PathRuleMatcher::build(&with_builtin(
+                &BUILTIN_AUTHENTICATED_PATHS,
+                &auth.authenticated_paths,
+            ))?,
+        })
+    }
+
+    /// 判断请求的处理级别，同时命中时公开规则优先
+    pub fn access_level(&self, method: &str, path: &str) -> AccessLevel {
+        if self.public.is_match(method, path) {
+            AccessLevel::Public
+        } else if self.authenticated.is_match(method, path) {
+            AccessLevel::Authenticated
+        } else {
+            AccessLevel::Authorized
+        }
+    }
+}
+
+fn with_builtin(builtin: &[(&str, &str)], configured: &[PathRule]) -> Vec<PathRule> {
+    builtin
+        .iter()
+        .map(|(method, path)| PathRule {
+            path: path.to_string(),
+            methods: Some(method)
+                .filter(|method| !method.is_empty())
+                .map(|method| vec![method.to_string()])
+                .unwrap_or_default(),
+        })
+        .chain(configured.iter().cloned())
+        .collect()
+}
+
+#[derive(Debug)]
+struct PathRuleMatcher {
+    // (编译后的路径, 限定的请求方法)
+    rules: Vec<(Regex, Vec<String>)>,
+}
+
+impl PathRuleMatcher {
+    fn build(rules: &[PathRule]) -> Result<Self, String> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let pattern = if rule.path.starts_with('^') {
+                    rule.path.clone()
+                } else {
+                    glob_to_regex(&rule.path)
+                };
+                Regex::new(&pattern)
+                    .map(|regex| (regex, rule.methods.clone()))
+                    .map_err(|err| format!("无效的路径规则 {}: {}", rule.path, err))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(PathRuleMatcher { rules })
+    }
+
+    fn is_match(&self, method: &str, path: &str) -> bool {
+        self.rules.iter().any(|(regex, methods)| {
+            (methods.is_empty() || methods.iter().any(|item| item.eq_ignore_ascii_case(method)))
+                && regex.is_match(path)
+        })
+    }
+}
+
+// 把通配符路径转换为正则表达式，`**`匹配任意多级路径，`*`匹配单级路径中的任意字符
+fn glob_to_regex(glob: &str) -> String {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                pattern.push_str(".*");
+            }
+            '*' => pattern.push_str("[^/]*"),
+            _ => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    pattern
+}
+
+lazy_static! {
+    pub static ref ACCESS_RULES: AccessRules = AccessRules::from_config(&CFG.auth).unwrap();
+}
+
+// 判断请求方法是否在资源允许的方法列表中，列表为空或包含 * 时允许全部方法
+fn method_matches(resource_method: Option<&str>, method: &str) -> bool {
+    match resource_method.map(str::trim) {
+        None | Some("") => true,
+        Some(methods) => methods
+            .split(',')
+            .map(str::trim)
+            .any(|item| item == "*" || item.eq_ignore_ascii_case(method)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rel(role_name: &str, resource_value: &str, resource_method: Option<&str>) -> RoleRelPathDto {
+        RoleRelPathDto {
+            role_name: role_name.to_string(),
+            resource_value: resource_value.to_string(),
+            resource_method: resource_method.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_is_allowed() {
+        let matcher = PermissionMatcher::build(
+            vec![
+                rel("reader", "^/user/.*$", Some("GET, head")),
+                rel("reader", "^/role/.*$", Some("GET")),
+                rel("admin", "^/user/.*$", None),
+            ],
+            &[],
+        );
+        let reader = vec!["reader".to_string()];
+        let admin = vec!["admin".to_string()];
+
+        assert!(matcher.is_allowed(&reader, "GET", "/user/1"));
+        assert!(matcher.is_allowed(&reader, "HEAD", "/user/1"));
+        assert!(matcher.is_allowed(&reader, "GET", "/role/1"));
+        assert!(!matcher.is_allowed(&reader, "DELETE", "/user/1"));
+        assert!(matcher.is_allowed(&admin, "DELETE", "/user/1"));
+        assert!(!matcher.is_allowed(&admin, "GET", "/role/1"));
+        assert!(!matcher.is_allowed(&["guest".to_string()], "GET", "/user/1"));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let matcher = PermissionMatcher::build(
+            vec![
+                rel("reader", "^/user/(.*$", None),
+                rel("reader", "^/role/.*$", None),
+            ],
+            &[],
+        );
+        let reader = vec!["reader".to_string()];
+
+        // 无效规则被剔除，同角色的其他规则仍然生效
+        assert!(matcher.is_allowed(&reader, "GET", "/role/1"));
+        assert!(!matcher.is_allowed(&reader, "GET", "/user/1"));
+        let snapshot = matcher.snapshot();
+        assert_eq!(1, snapshot.invalid_patterns.len());
+        assert_eq!("^/user/(.*$", snapshot.invalid_patterns[0].resource_value);
+        assert_eq!(1, snapshot.role_rel_resource["reader"].len());
+    }
+
+    fn inherit(role_name: &str, parent_role_name: &str) -> RoleInheritDto {
+        RoleInheritDto {
+            role_name: role_name.to_string(),
+            parent_role_name: parent_role_name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_inherited_permission() {
+        let matcher = PermissionMatcher::build(
+            vec![
+                rel("staff", "^/user/.*$", Some("GET")),
+                rel("manager", "^/role/.*$", None),
+            ],
+            &[inherit("manager", "staff"), inherit("director", "manager")],
+        );
+        let director = vec!["director".to_string()];
+
+        assert!(matcher.is_allowed(&director, "GET", "/user/1"));
+        assert!(matcher.is_allowed(&director, "DELETE", "/role/1"));
+        assert!(!matcher.is_allowed(&director, "DELETE", "/user/1"));
+        assert!(!matcher.is_allowed(&["staff".to_string()], "GET", "/role/1"));
+        assert_eq!(
+            vec!["director", "manager", "staff"],
+            matcher.expand_roles(&director)
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        let matcher = PermissionMatcher::build(
+            vec![
+                rel("staff", "^/user/.*$", Some("GET")),
+                rel("manager", "^/role/.*$", None),
+            ],
+            &[inherit("manager", "staff")],
+        );
+        let evaluations = matcher.explain(&["manager".to_string()], "DELETE", "/user/1");
+
+        assert_eq!(2, evaluations.len());
+        assert_eq!("manager", evaluations[0].role_name);
+        assert!(!evaluations[0].path_matched);
+        assert!(evaluations[0].method_matched);
+        assert_eq!("staff", evaluations[1].role_name);
+        assert!(evaluations[1].path_matched);
+        assert!(!evaluations[1].method_matched);
+    }
+
+    #[test]
+    fn test_flatten_role_inherit() {
+        // a -> b -> c -> a 构成环，c 另外继承 d，e 继承自身
+        let (ancestors, cycles) = flatten_role_inherit(&[
+            inherit("b", "c"),
+            inherit("a", "b"),
+            inherit("c", "a"),
+            inherit("c", "d"),
+            inherit("e", "e"),
+        ]);
+
+        assert_eq!(vec!["b", "c", "d"], ancestors["a"]);
+        assert_eq!(vec!["a", "b", "d"], ancestors["c"]);
+        assert!(ancestors["e"].is_empty());
+        assert_eq!(vec![vec!["a", "b", "c"], vec!["e"]], cycles);
+    }
+
+    #[test]
+    fn test_access_level() {
+        let rules = AccessRules::from_config(&Auth {
+            public_paths: vec![
+                PathRule {
+                    path: "/docs/**".to_string(),
+                    methods: vec!["get".to_string()],
+                },
+                PathRule {
+                    path: "^/health$".to_string(),
+                    methods: vec![],
+                },
+            ],
+            authenticated_paths: vec![PathRule {
+                path: "/test/*".to_string(),
+                methods: vec![],
+            }],
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            AccessLevel::Public,
+            rules.access_level("POST", "/auth/login")
+        );
+        assert_eq!(
+            AccessLevel::Authorized,
+            rules.access_level("GET", "/auth/login")
+        );
+        assert_eq!(
+            AccessLevel::Public,
+            rules.access_level("GET", "/docs/a/b.html")
+        );
+        assert_eq!(
+            AccessLevel::Authorized,
+            rules.access_level("POST", "/docs/a")
+        );
+        assert_eq!(AccessLevel::Public, rules.access_level("DELETE", "/health"));
+        assert_eq!(
+            AccessLevel::Authenticated,
+            rules.access_level("GET", "/auth/me")
+        );
+        assert_eq!(
+            AccessLevel::Authenticated,
+            rules.access_level("GET", "/auth/me/menus")
+        );
+        assert_eq!(
+            AccessLevel::Authenticated,
+            rules.access_level("GET", "/test/hello")
+        );
+        assert_eq!(
+            AccessLevel::Authorized,
+            rules.access_level("GET", "/test/a/b")
+        );
+        assert_eq!(
+            AccessLevel::Authorized,
+            rules.access_level("GET", "/sys/user")
+        );
+    }
+
+    #[test]
+    fn test_invalid_path_rule() {
+        let auth = Auth {
+            public_paths: vec![PathRule {
+                path: "^/docs/(".to_string(),
+                methods: vec![],
+            }],
+            ..Default::default()
+        };
+        assert!(AccessRules::from_config(&auth).is_err());
+    }
+
+    #[test]
+    fn test_method_matches() {
+        assert!(method_matches(None, "POST"));
+        assert!(method_matches(Some(" "), "POST"));
+        assert!(method_matches(Some("*"), "POST"));
+        assert!(method_matches(Some("get,post"), "POST"));
+        assert!(!method_matches(Some("GET"), "POST"));
+    }
+}
@@ -0,0 +1,427 @@
+use crate::dto::{
+    page_dto::{PageVo, page_request},
+    resource_dto::{MenuVo, ResourceQueryDto, ResourceSaveDto, ResourceVo},
+};
+use crate::model::{
+    constance::{NOT_DELETED, RESOURCE_TYPE_BUTTON, RESOURCE_TYPE_PAGE, RESOURCE_TYPE_PATH},
+    error::{AppError, AppResult},
+    user::{
+        Resource, RoleRelResource, clear_resource_parent, select_resources_by_role_id,
+        select_resources_by_role_names, soft_delete_resource, soft_delete_role_rel_resource,
+        soft_delete_role_rel_resource_by_resource_id,
+    },
+};
+use crate::service::{
+    auth_service::reload_permission_matcher, permission_service::PermissionMatcher,
+    role_service::find_role,
+};
+use log::info;
+use rbatis::{RBatis, rbdc::DateTime};
+use rbs::value;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::{PoisonError, RwLock};
+
+// 资源允许配置的请求方法
+const HTTP_METHODS: [&str; 7] = ["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS"];
+// 表示允许全部请求方法
+const ANY_METHOD: &str = "*";
+
+/// 分页查询资源
+pub async fn page_resources(
+    rb: &RBatis,
+    query: &ResourceQueryDto,
+) -> AppResult<PageVo<ResourceVo>> {
+    let page_req = page_request(query.page_no, query.page_size);
+    let page = Resource::select_page_by_query(rb, &page_req, query).await?;
+    Ok(PageVo::from_page(page, ResourceVo::from))
+}
+
+/// 根据ID查询资源
+pub async fn get_resource(rb: &RBatis, id: u64) -> AppResult<ResourceVo> {
+    find_resource(rb, id).await.map(ResourceVo::from)
+}
+
+/// 创建资源
+///
+/// 路径类型资源的值必须是有效的正则表达式，请求方法必须是标准HTTP方法或`*`
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `permission_matcher` - 应用状态中的权限匹配器
+/// * `operator_id` - 当前操作人ID
+/// * `dto` - 资源内容，`resource_value`与`resource_type`必填
+pub async fn create_resource(
+    rb: &RBatis,
+    permission_matcher: &RwLock<PermissionMatcher>,
+    operator_id: u64,
+    dto: ResourceSaveDto,
+) -> AppResult<ResourceVo> {
+    let (Some(resource_value), Some(resource_type)) = (dto.resource_value, dto.resource_type)
+    else {
+        return Err(AppError::BadRequest("资源值与资源类型不能为空".to_string()));
+    };
+    let resource_method = validate_resource(
+        resource_type,
+        &resource_value,
+        dto.resource_method.as_deref(),
+    )?;
+    if let Some(parent_id) = dto.parent_id {
+        find_resource(rb, parent_id).await?;
+    }
+
+    let mut resource = Resource::init_create(
+        resource_value,
+        dto.resource_desc,
+        resource_type,
+        resource_method,
+        dto.parent_id,
+        operator_id,
+    );
+    let res = Resource::insert(rb, &resource).await?;
+    if res.rows_affected == 0 {
+        return Err(AppError::Internal("保存资源失败: 数据写入失败".to_string()));
+    }
+    resource.id = res.last_insert_id.as_u64();
+    reload_permission_matcher(rb, permission_matcher).await?;
+    info!("资源 {:?} 被 {} 创建", resource.resource_value, operator_id);
+    Ok(ResourceVo::from(resource))
+}
+
+/// 修改资源，未提供的字段保持不变
+///
+/// `resource_method` 为空字符串或`*`时取消请求方法限制，`clear_parent`为 true 时取消上级资源
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `permission_matcher` - 应用状态中的权限匹配器
+/// * `operator_id` - 当前操作人ID
+/// * `id` - 资源ID
+/// * `dto` - 修改内容
+pub async fn update_resource(
+    rb: &RBatis,
+    permission_matcher: &RwLock<PermissionMatcher>,
+    operator_id: u64,
+    id: u64,
+    dto: ResourceSaveDto,
+) -> AppResult<ResourceVo> {
+    let old = find_resource(rb, id).await?;
+    // 以修改后的完整内容校验
+    let resource_type = dto.resource_type.or(old.resource_type).unwrap_or_default();
+    let resource_value = dto
+        .resource_value
+        .clone()
+        .or(old.resource_value)
+        .unwrap_or_default();
+    let resource_method = validate_resource(
+        resource_type,
+        &resource_value,
+        dto.resource_method
+            .as_deref()
+            .or(old.resource_method.as_deref()),
+    )?;
+    if dto.clear_parent && dto.parent_id.is_some() {
+        return Err(AppError::BadRequest(
+            "parent_id 与 clear_parent 不能同时提供".to_string(),
+        ));
+    }
+    if let Some(parent_id) = dto.parent_id {
+        check_parent(rb, id, parent_id).await?;
+    }
+
+    let resource = Resource {
+        resource_value: dto.resource_value,
+        resource_desc: dto.resource_desc,
+        resource_type: dto.resource_type,
+        // 提供了请求方法时总是写入规范化后的值，不限制方法时写入*，因为空值不会被更新
+        resource_method: dto
+            .resource_method
+            .as_ref()
+            .map(|_| resource_method.unwrap_or_else(|| ANY_METHOD.to_string())),
+        parent_id: dto.parent_id,
+        update_time: Some(DateTime::now()),
+        updater_id: Some(operator_id),
+        ..Default::default()
+    };
+    Resource::update_by_map(rb, &resource, value! {"id": id, "is_delete": NOT_DELETED}).await?;
+    if dto.clear_parent {
+        clear_resource_parent(rb, operator_id, id).await?;
+    }
+    reload_permission_matcher(rb, permission_matcher).await?;
+    get_resource(rb, id).await
+}
+
+// 校验上级资源存在，且不是该资源自身或其下级资源，避免形成循环
+async fn check_parent(rb: &RBatis, id: u64, parent_id: u64) -> AppResult<()> {
+    let mut visited = HashSet::new();
+    let mut current = Some(parent_id);
+    while let Some(current_id) = current {
+        if current_id == id {
+            return Err(AppError::BadRequest(
+                "上级资源不能是自身或其下级资源".to_string(),
+            ));
+        }
+        // 已有数据中存在循环时停止向上查找
+        if !visited.insert(current_id) {
+            break;
+        }
+        current = find_resource(rb, current_id)
+            .await?
+            .parent_id
+            .filter(|ancestor_id| *ancestor_id != 0);
+    }
+    Ok(())
+}
+
+/// 软删除资源，同时取消所有角色对该资源的授权。存在下级资源时不允许删除
+pub async fn delete_resource(
+    rb: &RBatis,
+    permission_matcher: &RwLock<PermissionMatcher>,
+    operator_id: u64,
+    id: u64,
+) -> AppResult<()> {
+    if !Resource::select_by_parent_id(rb, id).await?.is_empty() {
+        return Err(AppError::Conflict("请先删除下级资源".to_string()));
+    }
+    let res = soft_delete_resource(rb, operator_id, id).await?;
+    if res.rows_affected == 0 {
+        return Err(AppError::NotFound("资源不存在".to_string()));
+    }
+    soft_delete_role_rel_resource_by_resource_id(rb, operator_id, id).await?;
+
+    reload_permission_matcher(rb, permission_matcher).await?;
+    info!("资源 {} 被 {} 删除", id, operator_id);
+    Ok(())
+}
+
+/// 查询角色被授权的资源
+pub async fn list_role_resources(rb: &RBatis, role_id: u64) -> AppResult<Vec<ResourceVo>> {
+    find_role(rb, role_id).await?;
+    let resources = select_resources_by_role_id(rb, role_id).await?;
+    Ok(resources.into_iter().map(ResourceVo::from).collect())
+}
+
+/// 为角色授权资源，已授权的资源会被跳过
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `permission_matcher` - 应用状态中的权限匹配器
+/// * `operator_id` - 当前操作人ID
+/// * `role_id` - 角色ID
+/// * `resource_ids` - 需要授权的资源ID列表
+pub async fn grant_resources(
+    rb: &RBatis,
+    permission_matcher: &RwLock<PermissionMatcher>,
+    operator_id: u64,
+    role_id: u64,
+    resource_ids: &[u64],
+) -> AppResult<()> {
+    if resource_ids.is_empty() {
+        return Err(AppError::BadRequest("资源列表不能为空".to_string()));
+    }
+    find_role(rb, role_id).await?;
+    let mut resource_ids = resource_ids.to_vec();
+    resource_ids.sort_unstable();
+    resource_ids.dedup();
+    for resource_id in &resource_ids {
+        find_resource(rb, *resource_id).await?;
+    }
+
+    let mut rels = Vec::new();
+    for resource_id in &resource_ids {
+        if RoleRelResource::select_by_role_and_resource(rb, role_id, *resource_id)
+            .await?
+            .is_none()
+        {
+            rels.push(RoleRelResource::init_create(
+                role_id,
+                *resource_id,
+                operator_id,
+            ));
+        }
+    }
+    if !rels.is_empty() {
+        RoleRelResource::insert_batch(rb, &rels, rels.len() as u64).await?;
+        reload_permission_matcher(rb, permission_matcher).await?;
+    }
+    info!(
+        "角色 {} 被 {} 授权资源 {:?}",
+        role_id, operator_id, resource_ids
+    );
+    Ok(())
+}
+
+/// 取消角色对资源的授权
+pub async fn revoke_resource(
+    rb: &RBatis,
+    permission_matcher: &RwLock<PermissionMatcher>,
+    operator_id: u64,
+    role_id: u64,
+    resource_id: u64,
+) -> AppResult<()> {
+    let res = soft_delete_role_rel_resource(rb, operator_id, role_id, resource_id).await?;
+    if res.rows_affected == 0 {
+        return Err(AppError::NotFound("角色未被授权该资源".to_string()));
+    }
+    reload_permission_matcher(rb, permission_matcher).await?;
+    info!(
+        "角色 {} 的资源 {} 授权被 {} 取消",
+        role_id, resource_id, operator_id
+    );
+    Ok(())
+}
+
+/// 查询角色列表被授权的页面与按钮资源，并按`parent_id`组装为树
+///
+/// 上级资源未被授权时，该资源作为根节点返回；角色继承的父角色资源同样可见
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `permission_matcher` - 应用状态中的权限匹配器，用于展开角色继承关系
+/// * `roles` - 当前用户令牌中的角色列表
+pub async fn list_menus(
+    rb: &RBatis,
+    permission_matcher: &RwLock<PermissionMatcher>,
+    roles: &[String],
+) -> AppResult<Vec<MenuVo>> {
+    if roles.is_empty() {
+        return Ok(Vec::new());
+    }
+    let roles = permission_matcher
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .expand_roles(roles);
+    let resources =
+        select_resources_by_role_names(rb, &roles, &[RESOURCE_TYPE_PAGE, RESOURCE_TYPE_BUTTON])
+            .await?;
+    Ok(build_menu_tree(resources))
+}
+
+// 按parent_id把资源组装为树，同级节点保持传入顺序
+fn build_menu_tree(resources: Vec<Resource>) -> Vec<MenuVo> {
+    let ids: HashSet<u64> = resources.iter().filter_map(|item| item.id).collect();
+    let mut roots = Vec::new();
+    let mut children: HashMap<u64, Vec<MenuVo>> = HashMap::new();
+    for node in resources.into_iter().map(MenuVo::from) {
+        match node.parent_id.filter(|parent_id| ids.contains(parent_id)) {
+            Some(parent_id) => children.entry(parent_id).or_default().push(node),
+            None => roots.push(node),
+        }
+    }
+    for root in roots.iter_mut() {
+        attach_children(root, &mut children);
+    }
+    roots
+}
+
+fn attach_children(node: &mut MenuVo, children: &mut HashMap<u64, Vec<MenuVo>>) {
+    if let Some(mut list) = children.remove(&node.id) {
+        for child in list.iter_mut() {
+            attach_children(child, children);
+        }
+        node.children = list;
+    }
+}
+
+// 校验资源内容，返回规范化后的请求方法（大写，逗号分隔）
+fn validate_resource(
+    resource_type: u8,
+    resource_value: &str,
+    resource_method: Option<&str>,
+) -> AppResult<Option<String>> {
+    if !(RESOURCE_TYPE_PATH..=RESOURCE_TYPE_BUTTON).contains(&resource_type) {
+        return Err(AppError::BadRequest(format!(
+            "无效的资源类型: {}",
+            resource_type
+        )));
+    }
+    if resource_value.trim().is_empty() {
+        return Err(AppError::BadRequest("资源值不能为空".to_string()));
+    }
+    // 路径资源的值会被编译为正则表达式，写入前先校验
+    if resource_type == RESOURCE_TYPE_PATH {
+        Regex::new(resource_value)
+            .map_err(|err| AppError::BadRequest(format!("无效的正则表达式: {}", err)))?;
+    }
+
+    let Some(resource_method) = resource_method.map(str::trim).filter(|m| !m.is_empty()) else {
+        return Ok(None);
+    };
+    let mut methods = Vec::new();
+    for method in resource_method.split(',').map(str::trim) {
+        let method = method.to_ascii_uppercase();
+        if method != "*" && !HTTP_METHODS.contains(&method.as_str()) {
+            return Err(AppError::BadRequest(format!("无效的请求方法: {}", method)));
+        }
+        if !methods.contains(&method) {
+            methods.push(method);
+        }
+    }
+    // 包含*时其余方法没有意义
+    if methods.iter().any(|method| method == ANY_METHOD) {
+        return Ok(Some(ANY_METHOD.to_string()));
+    }
+    Ok(Some(methods.join(",")))
+}
+
+async fn find_resource(rb: &RBatis, id: u64) -> AppResult<Resource> {
+    Resource::select_by_id(rb, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("资源 {} 不存在", id)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn resource(id: u64, parent_id: Option<u64>) -> Resource {
+        Resource {
+            id: Some(id),
+            resource_value: Some(format!("menu:{}", id)),
+            resource_type: Some(RESOURCE_TYPE_PAGE),
+            parent_id,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_menu_tree() {
+        let tree = build_menu_tree(vec![
+            resource(1, None),
+            resource(2, Some(1)),
+            resource(3, Some(2)),
+            resource(4, Some(1)),
+            resource(5, Some(0)),
+            // 上级资源未被授权
+            resource(6, Some(99)),
+        ]);
+
+        let root_ids: Vec<u64> = tree.iter().map(|node| node.id).collect();
+        assert_eq!(vec![1, 5, 6], root_ids);
+        let child_ids: Vec<u64> = tree[0].children.iter().map(|node| node.id).collect();
+        assert_eq!(vec![2, 4], child_ids);
+        assert_eq!(3, tree[0].children[0].children[0].id);
+    }
+
+    #[test]
+    fn test_validate_resource() {
+        assert_eq!(
+            Some("GET,HEAD".to_string()),
+            validate_resource(RESOURCE_TYPE_PATH, "^/user/.*$", Some("get, head,GET")).unwrap()
+        );
+        assert_eq!(
+            None,
+            validate_resource(RESOURCE_TYPE_PATH, "^/user/.*$", Some(" ")).unwrap()
+        );
+        assert_eq!(
+            Some("*".to_string()),
+            validate_resource(RESOURCE_TYPE_PATH, "^/user/.*$", Some("GET,*")).unwrap()
+        );
+        assert!(validate_resource(RESOURCE_TYPE_PATH, "^/user/(.*$", None).is_err());
+        assert!(validate_resource(RESOURCE_TYPE_PATH, "^/user/.*$", Some("FETCH")).is_err());
+        assert!(validate_resource(RESOURCE_TYPE_PATH, " ", None).is_err());
+        assert!(validate_resource(0, "^/user/.*$", None).is_err());
+        // 页面与按钮资源的值不是正则表达式
+        assert!(validate_resource(RESOURCE_TYPE_BUTTON, "user:(add", None).is_ok());
+    }
+}
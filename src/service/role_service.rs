@@ -0,0 +1,312 @@
+use crate::dto::{
+    page_dto::{PageVo, page_request},
+    role_dto::{RoleCreateDto, RoleQueryDto, RoleUpdateDto, RoleVo},
+    user_dto::UserVo,
+};
+use crate::model::{
+    constance::NOT_DELETED,
+    error::{AppError, AppResult},
+    user::{
+        Role, RoleInherit, UserRelRole, select_all_role_inherit, select_parent_roles_by_role_id,
+        select_roles_by_user_id, select_users_by_role_id, soft_delete_role,
+        soft_delete_role_inherit, soft_delete_role_inherit_by_role_id,
+        soft_delete_role_rel_resource_by_role_id, soft_delete_user_rel_role,
+        soft_delete_user_rel_role_by_role_id,
+    },
+};
+use crate::service::{
+    auth_service::reload_permission_matcher,
+    permission_service::{PermissionMatcher, flatten_role_inherit},
+    token_revocation_service::TokenRevocationStore,
+    user_service::find_user,
+};
+use crate::util::config_util::CFG;
+use log::{info, warn};
+use rbatis::{RBatis, rbdc::DateTime};
+use rbs::value;
+use std::sync::RwLock;
+
+/// 分页查询角色
+pub async fn page_roles(rb: &RBatis, query: &RoleQueryDto) -> AppResult<PageVo<RoleVo>> {
+    let page_req = page_request(query.page_no, query.page_size);
+    let page = Role::select_page_by_query(rb, &page_req, query).await?;
+    Ok(PageVo::from_page(page, RoleVo::from))
+}
+
+/// 根据ID查询角色
+pub async fn get_role(rb: &RBatis, id: u64) -> AppResult<RoleVo> {
+    find_role(rb, id).await.map(RoleVo::from)
+}
+
+/// 创建角色
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `operator_id` - 当前操作人ID
+/// * `dto` - 角色名与描述，角色名不能与未删除的角色重复
+pub async fn create_role(rb: &RBatis, operator_id: u64, dto: RoleCreateDto) -> AppResult<RoleVo> {
+    let role_name = dto.role_name.trim().to_string();
+    check_role_name(rb, &role_name, None).await?;
+
+    let mut role = Role::init_create(role_name, dto.role_desc, operator_id);
+    let res = Role::insert(rb, &role).await?;
+    if res.rows_affected == 0 {
+        return Err(AppError::Internal("保存角色失败: 数据写入失败".to_string()));
+    }
+    role.id = res.last_insert_id.as_u64();
+    info!("角色 {:?} 被 {} 创建", role.role_name, operator_id);
+    Ok(RoleVo::from(role))
+}
+
+/// 修改角色名或描述
+///
+/// 令牌中保存的是角色名，重命名后会重新加载权限规则，并递增拥有该角色的用户的权限版本号，
+/// 这些用户需重新获取令牌
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `permission_matcher` - 应用状态中的权限匹配器
+/// * `revocation_store` - 令牌作废存储
+/// * `operator_id` - 当前操作人ID
+/// * `id` - 角色ID
+/// * `dto` - 修改内容，字段为空时保持不变
+pub async fn update_role(
+    rb: &RBatis,
+    permission_matcher: &RwLock<PermissionMatcher>,
+    revocation_store: &TokenRevocationStore,
+    operator_id: u64,
+    id: u64,
+    dto: RoleUpdateDto,
+) -> AppResult<RoleVo> {
+    let old = find_role(rb, id).await?;
+    let role_name = dto.role_name.map(|name| name.trim().to_string());
+    let renamed = role_name.is_some() && role_name != old.role_name;
+    if let Some(role_name) = role_name.as_deref().filter(|_| renamed) {
+        check_role_name(rb, role_name, Some(id)).await?;
+    }
+
+    let role = Role {
+        role_name,
+        role_desc: dto.role_desc,
+        update_time: Some(DateTime::now()),
+        updater_id: Some(operator_id),
+        ..Default::default()
+    };
+    Role::update_by_map(rb, &role, value! {"id": id, "is_delete": NOT_DELETED}).await?;
+    if renamed {
+        reload_permission_matcher(rb, permission_matcher).await?;
+        revocation_store.bump_role_auth_version(rb, id).await?;
+    }
+    get_role(rb, id).await
+}
+
+/// 软删除角色，同时取消该角色与用户、资源、父子角色的全部关联并重新加载权限规则，
+/// 拥有该角色的用户已签发的令牌失效
+pub async fn delete_role(
+    rb: &RBatis,
+    permission_matcher: &RwLock<PermissionMatcher>,
+    revocation_store: &TokenRevocationStore,
+    operator_id: u64,
+    id: u64,
+) -> AppResult<()> {
+    let res = soft_delete_role(rb, operator_id, id).await?;
+    if res.rows_affected == 0 {
+        return Err(AppError::NotFound("角色不存在".to_string()));
+    }
+    // 需在取消用户关联之前递增，否则查不到拥有该角色的用户
+    revocation_store.bump_role_auth_version(rb, id).await?;
+    soft_delete_user_rel_role_by_role_id(rb, operator_id, id).await?;
+    soft_delete_role_rel_resource_by_role_id(rb, operator_id, id).await?;
+    soft_delete_role_inherit_by_role_id(rb, operator_id, id, id).await?;
+
+    reload_permission_matcher(rb, permission_matcher).await?;
+    info!("角色 {} 被 {} 删除", id, operator_id);
+    Ok(())
+}
+
+/// 查询拥有该角色的用户
+pub async fn list_role_users(rb: &RBatis, id: u64) -> AppResult<Vec<UserVo>> {
+    find_role(rb, id).await?;
+    let users = select_users_by_role_id(rb, id).await?;
+    Ok(users.into_iter().map(UserVo::from).collect())
+}
+
+/// 查询用户拥有的角色
+pub async fn list_user_roles(rb: &RBatis, user_id: u64) -> AppResult<Vec<RoleVo>> {
+    find_user(rb, user_id).await?;
+    let roles = select_roles_by_user_id(rb, user_id).await?;
+    Ok(roles.into_iter().map(RoleVo::from).collect())
+}
+
+/// 为用户分配角色，已拥有该角色的用户会被跳过
+///
+/// 令牌中保存了角色列表，分配后会递增新获得角色的用户的权限版本号，这些用户需重新获取令牌
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `revocation_store` - 令牌作废存储
+/// * `operator_id` - 当前操作人ID
+/// * `id` - 角色ID
+/// * `user_ids` - 需要分配角色的用户ID列表
+pub async fn assign_role(
+    rb: &RBatis,
+    revocation_store: &TokenRevocationStore,
+    operator_id: u64,
+    id: u64,
+    user_ids: &[u64],
+) -> AppResult<()> {
+    if user_ids.is_empty() {
+        return Err(AppError::BadRequest("用户列表不能为空".to_string()));
+    }
+    find_role(rb, id).await?;
+    let mut user_ids = user_ids.to_vec();
+    user_ids.sort_unstable();
+    user_ids.dedup();
+    for user_id in &user_ids {
+        find_user(rb, *user_id).await?;
+    }
+
+    let mut rels = Vec::new();
+    for user_id in &user_ids {
+        if UserRelRole::select_by_user_and_role(rb, *user_id, id)
+            .await?
+            .is_none()
+        {
+            rels.push(UserRelRole::init_create(*user_id, id, operator_id));
+        }
+    }
+    if !rels.is_empty() {
+        UserRelRole::insert_batch(rb, &rels, rels.len() as u64).await?;
+    }
+    for user_id in rels.iter().filter_map(|rel| rel.user_id) {
+        revocation_store.bump_auth_version(rb, user_id).await?;
+    }
+    info!("角色 {} 被 {} 分配给用户 {:?}", id, operator_id, user_ids);
+    Ok(())
+}
+
+/// 取消用户的角色，该用户已签发的令牌立即失效
+pub async fn revoke_role(
+    rb: &RBatis,
+    revocation_store: &TokenRevocationStore,
+    operator_id: u64,
+    id: u64,
+    user_id: u64,
+) -> AppResult<()> {
+    let res = soft_delete_user_rel_role(rb, operator_id, user_id, id).await?;
+    if res.rows_affected == 0 {
+        return Err(AppError::NotFound("用户未拥有该角色".to_string()));
+    }
+    revocation_store.bump_auth_version(rb, user_id).await?;
+    info!("用户 {} 的角色 {} 被 {} 取消", user_id, id, operator_id);
+    Ok(())
+}
+
+/// 查询角色直接继承的父角色
+pub async fn list_parent_roles(rb: &RBatis, id: u64) -> AppResult<Vec<RoleVo>> {
+    find_role(rb, id).await?;
+    let roles = select_parent_roles_by_role_id(rb, id).await?;
+    Ok(roles.into_iter().map(RoleVo::from).collect())
+}
+
+/// 为角色添加父角色，角色将继承父角色及其祖先的全部资源授权
+///
+/// 添加后会形成循环继承时拒绝添加
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `permission_matcher` - 应用状态中的权限匹配器
+/// * `operator_id` - 当前操作人ID
+/// * `id` - 角色ID
+/// * `parent_id` - 父角色ID
+pub async fn add_parent_role(
+    rb: &RBatis,
+    permission_matcher: &RwLock<PermissionMatcher>,
+    operator_id: u64,
+    id: u64,
+    parent_id: u64,
+) -> AppResult<()> {
+    let role = find_role(rb, id).await?;
+    let parent = find_role(rb, parent_id).await?;
+    if RoleInherit::select_by_role_and_parent(rb, id, parent_id)
+        .await?
+        .is_some()
+    {
+        return Err(AppError::Conflict("角色已继承该父角色".to_string()));
+    }
+    // 父角色就是自身或已经继承了该角色时，添加后会形成环
+    let (ancestors, _) = flatten_role_inherit(&select_all_role_inherit(rb).await?);
+    let role_name = role.role_name.unwrap_or_default();
+    let parent_name = parent.role_name.unwrap_or_default();
+    if id == parent_id
+        || ancestors
+            .get(&parent_name)
+            .is_some_and(|items| items.contains(&role_name))
+    {
+        return Err(AppError::BadRequest("不能形成循环继承".to_string()));
+    }
+
+    RoleInherit::insert(rb, &RoleInherit::init_create(id, parent_id, operator_id)).await?;
+    reload_permission_matcher(rb, permission_matcher).await?;
+    info!(
+        "角色 {} 被 {} 设置为继承角色 {}",
+        id, operator_id, parent_id
+    );
+    Ok(())
+}
+
+/// 取消角色对父角色的继承
+pub async fn remove_parent_role(
+    rb: &RBatis,
+    permission_matcher: &RwLock<PermissionMatcher>,
+    operator_id: u64,
+    id: u64,
+    parent_id: u64,
+) -> AppResult<()> {
+    let res = soft_delete_role_inherit(rb, operator_id, id, parent_id).await?;
+    if res.rows_affected == 0 {
+        return Err(AppError::NotFound("角色未继承该父角色".to_string()));
+    }
+    reload_permission_matcher(rb, permission_matcher).await?;
+    info!(
+        "角色 {} 对角色 {} 的继承被 {} 取消",
+        id, parent_id, operator_id
+    );
+    Ok(())
+}
+
+/// 检查配置的超级管理员角色是否有用户持有，没有时记录警告
+pub async fn check_super_role_holders(rb: &RBatis) -> AppResult<()> {
+    let Some(super_role) = CFG.auth.super_role.as_deref() else {
+        return Ok(());
+    };
+    let holders = match Role::select_by_role_name(rb, super_role).await? {
+        Some(Role { id: Some(id), .. }) => select_users_by_role_id(rb, id).await?.len(),
+        _ => 0,
+    };
+    if holders == 0 {
+        warn!("超级管理员角色 {} 未分配给任何用户", super_role);
+    } else {
+        info!("超级管理员角色 {} 已分配给 {} 个用户", super_role, holders);
+    }
+    Ok(())
+}
+
+// 校验角色名非空且未被其他角色使用
+async fn check_role_name(rb: &RBatis, role_name: &str, exclude_id: Option<u64>) -> AppResult<()> {
+    if role_name.is_empty() {
+        return Err(AppError::BadRequest("角色名不能为空".to_string()));
+    }
+    let exists = Role::select_by_role_name(rb, role_name).await?;
+    if exists.is_some_and(|role| role.id != exclude_id) {
+        return Err(AppError::Conflict("角色名已存在".to_string()));
+    }
+    Ok(())
+}
+
+// 查询未删除的角色，不存在时返回NotFound
+pub(crate) async fn find_role(rb: &RBatis, id: u64) -> AppResult<Role> {
+    Role::select_by_id(rb, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("角色不存在".to_string()))
+}
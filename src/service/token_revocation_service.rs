@@ -0,0 +1,283 @@
+use crate::model::constance::{
+    AUTH_VERSION_CACHE_SECOND, REVOCATION_CHECK_CACHE_SECOND, REVOKE_TYPE_TOKEN, REVOKE_TYPE_USER,
+};
+use crate::model::error::{AppError, AppResult};
+use crate::model::token::{
+    RevokedToken, delete_expired_revoked_token, revoke_refresh_token_by_user_id,
+};
+use crate::model::user::{User, bump_auth_version_by_role_id, bump_user_auth_version};
+use crate::util::{config_util::CFG, token_util::TokenClaims};
+use chrono::Utc;
+use dashmap::DashMap;
+use log::{error, info};
+use rbatis::{RBatis, rbdc::DateTime};
+
+/// 令牌作废存储
+///
+/// 作废记录同时保存在内存与数据库中：内存用于请求时的快速判定，
+/// 数据库用于服务重启后恢复以及多实例之间共享。
+/// 内存中未命中时会回查数据库，并在短时间内缓存未作废的结论，避免每个请求都访问数据库。
+/// 令牌中的权限版本号低于用户当前的版本号时同样视为已作废，用户版本号也在内存中短时间缓存
+#[derive(Debug, Default)]
+pub struct TokenRevocationStore {
+    // 已作废的令牌ID -> 令牌原始过期时间戳
+    revoked_jti: DashMap<String, usize>,
+    // 用户ID -> (签发时间早于该时间戳的令牌均失效, 记录过期时间戳)
+    revoked_user: DashMap<u64, (usize, usize)>,
+    // 令牌ID -> (用户ID, 最近一次回查数据库确认未作废的时间戳)
+    checked_jti: DashMap<String, (u64, i64)>,
+    // 用户ID -> (权限版本号, 从数据库读取的时间戳)
+    auth_versions: DashMap<u64, (u64, i64)>,
+}
+
+impl TokenRevocationStore {
+    /// 从数据库加载所有尚未过期的作废记录
+    pub async fn load(rb: &RBatis) -> Self {
+        let store = TokenRevocationStore::default();
+        match RevokedToken::select_unexpired(rb).await {
+            Ok(list) => {
+                info!("加载令牌作废记录: {} 条", list.len());
+                list.iter().for_each(|item| store.remember(item));
+            }
+            Err(err) => error!("令牌作废记录加载失败: {}", err),
+        }
+        store
+    }
+
+    /// 判断令牌是否已被作废
+    ///
+    /// # 参数
+    /// * `rb` - 数据库连接实例，用于内存未命中时回查
+    /// * `claims` - 已通过签名与过期校验的令牌声明
+    ///
+    /// # 返回
+    /// * `Ok(true)` 表示令牌已作废，`Ok(false)` 表示令牌仍然有效
+    pub async fn is_revoked(&self, rb: &RBatis, claims: &TokenClaims) -> AppResult<bool> {
+        if self.is_revoked_in_memory(claims) {
+            return Ok(true);
+        }
+        if claims.auth_version < self.auth_version(rb, claims.user_id).await? {
+            return Ok(true);
+        }
+
+        // 最近确认过未作废，直接放行
+        let now = Utc::now().timestamp();
+        let recently_checked = self
+            .checked_jti
+            .get(&claims.jti)
+            .is_some_and(|checked| now - checked.1 < REVOCATION_CHECK_CACHE_SECOND);
+        if recently_checked {
+            return Ok(false);
+        }
+
+        // 回查数据库，以获取其他实例写入的作废记录
+        let list =
+            RevokedToken::select_unexpired_by_jti_or_user(rb, &claims.jti, claims.user_id).await?;
+        list.iter().for_each(|item| self.remember(item));
+
+        let revoked = self.is_revoked_in_memory(claims);
+        if !revoked {
+            self.checked_jti
+                .insert(claims.jti.clone(), (claims.user_id, now));
+        }
+        Ok(revoked)
+    }
+
+    /// 作废单个令牌，记录保留到令牌原始过期时间
+    pub async fn revoke_token(&self, rb: &RBatis, claims: &TokenClaims) -> AppResult<()> {
+        let record = RevokedToken::init_create(
+            claims.user_id,
+            Some(claims.jti.clone()),
+            REVOKE_TYPE_TOKEN,
+            None,
+            DateTime::from_timestamp(claims.exp as i64),
+        );
+        self.save(rb, &record).await
+    }
+
+    /// 作废用户当前已签发的全部令牌，包括所有刷新令牌
+    ///
+    /// 签发时间早于当前秒的访问令牌都会失效，记录保留一个访问令牌有效期，
+    /// 之后这些令牌已自然过期，可以安全清理。
+    /// 同一秒内签发的令牌不受作废记录影响，因此同时递增用户的权限版本号，
+    /// 使作废前签发的令牌（包括用刷新令牌换取的令牌）全部失效，作废后签发的令牌不受影响
+    pub async fn revoke_user(&self, rb: &RBatis, user_id: u64) -> AppResult<()> {
+        let now = Utc::now().timestamp();
+        let record = RevokedToken::init_create(
+            user_id,
+            None,
+            REVOKE_TYPE_USER,
+            Some(now as u64),
+            DateTime::from_timestamp(now + CFG.jwt.access_expiration_time * 60),
+        );
+        self.save(rb, &record).await?;
+        // 先作废刷新令牌再递增版本号，作废前已开始的刷新读取到的是旧版本号
+        revoke_refresh_token_by_user_id(rb, user_id).await?;
+        self.bump_auth_version(rb, user_id).await
+    }
+
+    /// 递增用户的权限版本号，该用户已签发的令牌立即失效
+    ///
+    /// 本实例立即生效，其他实例在版本号缓存过期后生效
+    pub async fn bump_auth_version(&self, rb: &RBatis, user_id: u64) -> AppResult<()> {
+        bump_user_auth_version(rb, user_id).await?;
+        self.auth_versions.remove(&user_id);
+        Ok(())
+    }
+
+    /// 递增拥有该角色的全部用户的权限版本号
+    pub async fn bump_role_auth_version(&self, rb: &RBatis, role_id: u64) -> AppResult<()> {
+        let res = bump_auth_version_by_role_id(rb, role_id).await?;
+        if res.rows_affected > 0 {
+            self.auth_versions.clear();
+        }
+        Ok(())
+    }
+
+    /// 清理内存与数据库中已过期的作废记录
+    pub async fn purge_expired(&self, rb: &RBatis) {
+        let now = Utc::now().timestamp();
+        self.revoked_jti.retain(|_, exp| *exp as i64 > now);
+        self.revoked_user.retain(|_, (_, exp)| *exp as i64 > now);
+        self.checked_jti
+            .retain(|_, (_, checked_at)| now - *checked_at < REVOCATION_CHECK_CACHE_SECOND);
+        self.auth_versions
+            .retain(|_, (_, loaded_at)| now - *loaded_at < AUTH_VERSION_CACHE_SECOND);
+
+        match delete_expired_revoked_token(rb).await {
+            Ok(res) => {
+                if res.rows_affected > 0 {
+                    info!("清理过期令牌作废记录: {} 条", res.rows_affected);
+                }
+            }
+            Err(err) => error!("清理过期令牌作废记录失败: {}", err),
+        }
+    }
+
+    // 用户当前的权限版本号，缓存过期时回查数据库；用户不存在时为0，由其他校验拒绝
+    async fn auth_version(&self, rb: &RBatis, user_id: u64) -> AppResult<u64> {
+        let now = Utc::now().timestamp();
+        let cached = self
+            .auth_versions
+            .get(&user_id)
+            .filter(|entry| now - entry.1 < AUTH_VERSION_CACHE_SECOND)
+            .map(|entry| entry.0);
+        if let Some(version) = cached {
+            return Ok(version);
+        }
+        let version = User::select_by_id(rb, user_id)
+            .await?
+            .and_then(|user| user.auth_version)
+            .unwrap_or_default();
+        self.auth_versions.insert(user_id, (version, now));
+        Ok(version)
+    }
+
+    async fn save(&self, rb: &RBatis, record: &RevokedToken) -> AppResult<()> {
+        let res = RevokedToken::insert(rb, record).await?;
+        if res.rows_affected == 0 {
+            return Err(AppError::Internal(
+                "令牌作废记录保存失败: 数据写入失败".to_string(),
+            ));
+        }
+        self.remember(record);
+        Ok(())
+    }
+
+    fn remember(&self, record: &RevokedToken) {
+        let expire = record
+            .expire_time
+            .as_ref()
+            .map(|t| t.unix_timestamp() as usize)
+            .unwrap_or_default();
+        match (record.revoke_type, &record.jti, record.user_id) {
+            (Some(REVOKE_TYPE_TOKEN), Some(jti), _) => {
+                self.checked_jti.remove(jti);
+                self.revoked_jti.insert(jti.clone(), expire);
+            }
+            (Some(REVOKE_TYPE_USER), _, Some(user_id)) => {
+                let before = record.revoke_before.unwrap_or_default() as usize;
+                // 同一用户保留最晚的一次作废
+                let mut entry = self.revoked_user.entry(user_id).or_insert((before, expire));
+                if entry.0 < before {
+                    *entry = (before, expire);
+                }
+                drop(entry);
+                self.checked_jti
+                    .retain(|_, (checked_user_id, _)| *checked_user_id != user_id);
+            }
+            _ => {}
+        }
+    }
+
+    // 同一秒内签发的令牌不受作废记录影响，便于作废后立即签发新令牌，
+    // 作废前签发的同一秒令牌由权限版本号拒绝
+    fn is_revoked_in_memory(&self, claims: &TokenClaims) -> bool {
+        if self.revoked_jti.contains_key(&claims.jti) {
+            return true;
+        }
+        self.revoked_user
+            .get(&claims.user_id)
+            .is_some_and(|entry| claims.iat < entry.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn claims(user_id: u64, jti: &str, iat: usize) -> TokenClaims {
+        TokenClaims {
+            user_id,
+            username: "test".to_string(),
+            exp: iat + 60,
+            iat,
+            jti: jti.to_string(),
+            roles: vec![],
+            auth_version: 0,
+        }
+    }
+
+    #[test]
+    fn test_revoked_in_memory() {
+        let store = TokenRevocationStore::default();
+        let expire_time = DateTime::from_timestamp(Utc::now().timestamp() + 60);
+        store.remember(&RevokedToken::init_create(
+            1,
+            Some("a".to_string()),
+            REVOKE_TYPE_TOKEN,
+            None,
+            expire_time.clone(),
+        ));
+        store.remember(&RevokedToken::init_create(
+            2,
+            None,
+            REVOKE_TYPE_USER,
+            Some(100),
+            expire_time,
+        ));
+
+        assert!(store.is_revoked_in_memory(&claims(1, "a", 100)));
+        assert!(!store.is_revoked_in_memory(&claims(1, "b", 100)));
+        assert!(store.is_revoked_in_memory(&claims(2, "c", 99)));
+        assert!(!store.is_revoked_in_memory(&claims(2, "d", 100)));
+    }
+
+    #[actix_rt::test]
+    async fn test_stale_auth_version() {
+        // 未连接数据库，命中缓存时不会回查
+        let rb = RBatis::new();
+        let store = TokenRevocationStore::default();
+        let now = Utc::now().timestamp();
+        store.auth_versions.insert(1, (2, now));
+        store.checked_jti.insert("b".to_string(), (1, now));
+
+        let mut stale = claims(1, "a", 100);
+        stale.auth_version = 1;
+        assert!(store.is_revoked(&rb, &stale).await.unwrap());
+
+        let mut current = claims(1, "b", 100);
+        current.auth_version = 2;
+        assert!(!store.is_revoked(&rb, &current).await.unwrap());
+    }
+}
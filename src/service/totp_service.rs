@@ -0,0 +1,253 @@
+use crate::dto::totp_dto::{RecoveryCodesVo, TotpDisableDto, TotpSetupVo, TotpStatusVo};
+use crate::model::{
+    constance::{AUDIT_LOG_TARGET, TOTP_ENABLED},
+    error::{AppError, AppResult},
+    user::{
+        User, UserRecoveryCode, clear_user_totp, soft_delete_recovery_codes_by_user_id,
+        update_totp_last_step, use_recovery_code,
+    },
+};
+use crate::service::{
+    auth_service::check_current_password,
+    login_throttle_service::LoginAttemptTracker,
+    user_service::{find_user, update_by_id, updated_by},
+};
+use crate::util::{
+    config_util::CFG,
+    token_util::hash_opaque_token,
+    totp_util::{
+        generate_recovery_code, generate_totp_secret, normalize_recovery_code, otpauth_uri,
+        verify_totp,
+    },
+};
+use chrono::Utc;
+use log::info;
+use rbatis::RBatis;
+
+/// 查询用户的两步验证状态
+pub async fn get_status(rb: &RBatis, user_id: u64) -> AppResult<TotpStatusVo> {
+    let user = find_user(rb, user_id).await?;
+    let recovery_codes_remaining = if is_enabled(&user) {
+        UserRecoveryCode::select_unused_by_user_id(rb, user_id)
+            .await?
+            .len()
+    } else {
+        0
+    };
+    Ok(TotpStatusVo {
+        enabled: is_enabled(&user),
+        recovery_codes_remaining,
+    })
+}
+
+/// 开始绑定认证器
+///
+/// 生成新的密钥并保存为待确认状态，提交一次正确的动态验证码后才会启用。
+/// 重复调用会覆盖尚未确认的密钥
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `user_id` - 当前登录用户ID
+pub async fn setup_totp(rb: &RBatis, user_id: u64) -> AppResult<TotpSetupVo> {
+    let user = find_user(rb, user_id).await?;
+    if is_enabled(&user) {
+        return Err(AppError::Conflict("已启用两步验证".to_string()));
+    }
+    let secret = generate_totp_secret();
+    let pending = User {
+        totp_secret: Some(secret.clone()),
+        ..updated_by(user_id)
+    };
+    update_by_id(rb, &pending, user_id).await?;
+    let otpauth_uri = otpauth_uri(
+        &CFG.totp.issuer,
+        user.username.as_deref().unwrap_or_default(),
+        &secret,
+    );
+    Ok(TotpSetupVo {
+        secret,
+        otpauth_uri,
+    })
+}
+
+/// 确认绑定认证器并启用两步验证
+///
+/// 校验通过后启用两步验证，并生成一组恢复码，恢复码明文只在此时返回一次
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `user_id` - 当前登录用户ID
+/// * `code` - 认证器显示的动态验证码
+pub async fn confirm_totp(rb: &RBatis, user_id: u64, code: &str) -> AppResult<RecoveryCodesVo> {
+    let user = find_user(rb, user_id).await?;
+    if is_enabled(&user) {
+        return Err(AppError::Conflict("已启用两步验证".to_string()));
+    }
+    let Some(secret) = user.totp_secret.as_deref() else {
+        return Err(AppError::BadRequest("请先绑定认证器".to_string()));
+    };
+    verify_code(rb, user_id, secret, code).await?;
+    let enabled = User {
+        totp_enabled: Some(TOTP_ENABLED),
+        ..updated_by(user_id)
+    };
+    update_by_id(rb, &enabled, user_id).await?;
+    let recovery_codes = replace_recovery_codes(rb, user_id).await?;
+    info!(target: AUDIT_LOG_TARGET, "启用两步验证: user_id={}", user_id);
+    Ok(recovery_codes)
+}
+
+/// 关闭两步验证
+///
+/// 需要同时校验登录密码与动态验证码（或恢复码），关闭后清除密钥并作废全部恢复码
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `user_id` - 当前登录用户ID
+/// * `dto` - 登录密码与动态验证码
+/// * `login_tracker` - 登录失败计数器，密码或验证码错误计入失败次数
+/// * `ip` - 客户端IP
+pub async fn disable_totp(
+    rb: &RBatis,
+    user_id: u64,
+    dto: TotpDisableDto,
+    login_tracker: &LoginAttemptTracker,
+    ip: Option<&str>,
+) -> AppResult<()> {
+    let user = find_user(rb, user_id).await?;
+    if !is_enabled(&user) {
+        return Err(AppError::BadRequest("未启用两步验证".to_string()));
+    }
+    if !check_current_password(login_tracker, &user, &dto.password, ip).await? {
+        return Err(AppError::BadRequest("密码错误".to_string()));
+    }
+    let username = user.username.as_deref().unwrap_or_default();
+    let result =
+        verify_second_factor(rb, &user, dto.code.as_deref(), dto.recovery_code.as_deref()).await;
+    if matches!(result, Err(AppError::InvalidOtp)) {
+        login_tracker.record_failure(username, ip);
+    }
+    result?;
+    login_tracker.record_success(username);
+    reset_totp(rb, user_id, user_id).await
+}
+
+/// 重新生成恢复码，原有未使用的恢复码全部作废
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `user_id` - 当前登录用户ID
+/// * `code` - 认证器显示的动态验证码
+pub async fn regenerate_recovery_codes(
+    rb: &RBatis,
+    user_id: u64,
+    code: &str,
+) -> AppResult<RecoveryCodesVo> {
+    let user = find_user(rb, user_id).await?;
+    if !is_enabled(&user) {
+        return Err(AppError::BadRequest("未启用两步验证".to_string()));
+    }
+    verify_second_factor(rb, &user, Some(code), None).await?;
+    let recovery_codes = replace_recovery_codes(rb, user_id).await?;
+    info!(target: AUDIT_LOG_TARGET, "重新生成恢复码: user_id={}", user_id);
+    Ok(recovery_codes)
+}
+
+/// 清除用户的两步验证设置，用于用户自行关闭或管理员为丢失认证器的用户重置
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `operator_id` - 当前操作人ID
+/// * `user_id` - 被重置的用户ID
+pub async fn reset_totp(rb: &RBatis, operator_id: u64, user_id: u64) -> AppResult<()> {
+    let res = clear_user_totp(rb, operator_id, user_id).await?;
+    if res.rows_affected == 0 {
+        return Err(AppError::NotFound("用户不存在".to_string()));
+    }
+    soft_delete_recovery_codes_by_user_id(rb, operator_id, user_id).await?;
+    info!(
+        target: AUDIT_LOG_TARGET,
+        "关闭两步验证: user_id={}, operator_id={}", user_id, operator_id
+    );
+    Ok(())
+}
+
+/// 校验第二因素，动态验证码与恢复码提供其一即可
+///
+/// 同一时间步的动态验证码只能使用一次，恢复码使用后即作废
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `user` - 已启用两步验证的用户
+/// * `code` - 动态验证码
+/// * `recovery_code` - 恢复码
+///
+/// # 返回
+/// 校验失败时返回`AppError::InvalidOtp`
+pub async fn verify_second_factor(
+    rb: &RBatis,
+    user: &User,
+    code: Option<&str>,
+    recovery_code: Option<&str>,
+) -> AppResult<()> {
+    let user_id = user
+        .id
+        .ok_or_else(|| AppError::Internal("用户ID为空".to_string()))?;
+    match (code, recovery_code) {
+        (Some(code), _) => {
+            let Some(secret) = user.totp_secret.as_deref() else {
+                return Err(AppError::InvalidOtp);
+            };
+            verify_code(rb, user_id, secret, code).await
+        }
+        (None, Some(recovery_code)) => {
+            let code_hash = hash_opaque_token(&normalize_recovery_code(recovery_code));
+            let res = use_recovery_code(rb, user_id, &code_hash).await?;
+            if res.rows_affected == 0 {
+                return Err(AppError::InvalidOtp);
+            }
+            info!(target: AUDIT_LOG_TARGET, "使用恢复码: user_id={}", user_id);
+            Ok(())
+        }
+        (None, None) => Err(AppError::BadRequest("请提供动态验证码或恢复码".to_string())),
+    }
+}
+
+/// 用户是否已启用两步验证
+pub fn is_enabled(user: &User) -> bool {
+    user.totp_enabled == Some(TOTP_ENABLED) && user.totp_secret.is_some()
+}
+
+// 校验动态验证码并记录时间步，已使用过的时间步视为验证码错误
+async fn verify_code(rb: &RBatis, user_id: u64, secret: &str, code: &str) -> AppResult<()> {
+    let now = Utc::now().timestamp() as u64;
+    let Some(step) = verify_totp(secret, code, now, CFG.totp.allowed_skew_step) else {
+        return Err(AppError::InvalidOtp);
+    };
+    let res = update_totp_last_step(rb, step, user_id, step).await?;
+    if res.rows_affected == 0 {
+        return Err(AppError::InvalidOtp);
+    }
+    Ok(())
+}
+
+// 作废原有恢复码并生成一组新的恢复码，只保存摘要
+async fn replace_recovery_codes(rb: &RBatis, user_id: u64) -> AppResult<RecoveryCodesVo> {
+    soft_delete_recovery_codes_by_user_id(rb, user_id, user_id).await?;
+    let recovery_codes: Vec<String> = (0..CFG.totp.recovery_code_count)
+        .map(|_| generate_recovery_code())
+        .collect();
+    let records: Vec<UserRecoveryCode> = recovery_codes
+        .iter()
+        .map(|code| {
+            UserRecoveryCode::init_create(
+                user_id,
+                hash_opaque_token(&normalize_recovery_code(code)),
+            )
+        })
+        .collect();
+    if !records.is_empty() {
+        UserRecoveryCode::insert_batch(rb, &records, records.len() as u64).await?;
+    }
+    Ok(RecoveryCodesVo { recovery_codes })
+}
@@ -0,0 +1,189 @@
+use crate::dto::{
+    page_dto::{PageVo, page_request},
+    user_dto::{ResetPasswordDto, UserQueryDto, UserUpdateDto, UserVo},
+};
+use crate::model::{
+    constance::{NOT_DELETED, USER_STATUS_DISABLED, USER_STATUS_ENABLED},
+    error::{AppError, AppResult},
+    user::{User, soft_delete_user},
+};
+use crate::service::{password_policy_service, token_revocation_service::TokenRevocationStore};
+use crate::util::{config_util::CFG, password_util::hash_password};
+use log::info;
+use rbatis::{RBatis, rbdc::DateTime};
+use rbs::value;
+
+/// 分页查询用户
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `query` - 分页与过滤条件，用户名与姓名为模糊匹配
+pub async fn page_users(rb: &RBatis, query: &UserQueryDto) -> AppResult<PageVo<UserVo>> {
+    let page_req = page_request(query.page_no, query.page_size);
+    let page = User::select_page_by_query(rb, &page_req, query).await?;
+    Ok(PageVo::from_page(page, UserVo::from))
+}
+
+/// 根据ID查询用户
+pub async fn get_user(rb: &RBatis, id: u64) -> AppResult<UserVo> {
+    find_user(rb, id).await.map(UserVo::from)
+}
+
+/// 修改用户基本信息
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `operator_id` - 当前操作人ID
+/// * `id` - 被修改的用户ID
+/// * `dto` - 修改内容
+pub async fn update_user(
+    rb: &RBatis,
+    operator_id: u64,
+    id: u64,
+    dto: UserUpdateDto,
+) -> AppResult<UserVo> {
+    if dto.name.trim().is_empty() {
+        return Err(AppError::BadRequest("姓名不能为空".to_string()));
+    }
+    find_user(rb, id).await?;
+    let user = User {
+        name: Some(dto.name),
+        ..updated_by(operator_id)
+    };
+    update_by_id(rb, &user, id).await?;
+    get_user(rb, id).await
+}
+
+/// 启用或禁用用户，禁用时作废该用户已签发的全部令牌
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `revocation_store` - 令牌作废存储
+/// * `operator_id` - 当前操作人ID
+/// * `id` - 被修改的用户ID
+/// * `enabled` - true 启用，false 禁用
+pub async fn set_user_status(
+    rb: &RBatis,
+    revocation_store: &TokenRevocationStore,
+    operator_id: u64,
+    id: u64,
+    enabled: bool,
+) -> AppResult<()> {
+    if !enabled && operator_id == id {
+        return Err(AppError::BadRequest("不能禁用当前登录用户".to_string()));
+    }
+    find_user(rb, id).await?;
+    let status = if enabled {
+        USER_STATUS_ENABLED
+    } else {
+        USER_STATUS_DISABLED
+    };
+    let user = User {
+        status: Some(status),
+        ..updated_by(operator_id)
+    };
+    update_by_id(rb, &user, id).await?;
+    if !enabled {
+        revocation_store.revoke_user(rb, id).await?;
+    }
+    info!("用户 {} 状态被 {} 修改为 {}", id, operator_id, status);
+    Ok(())
+}
+
+/// 软删除用户，并作废该用户已签发的全部令牌
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `revocation_store` - 令牌作废存储
+/// * `operator_id` - 当前操作人ID
+/// * `id` - 被删除的用户ID
+pub async fn delete_user(
+    rb: &RBatis,
+    revocation_store: &TokenRevocationStore,
+    operator_id: u64,
+    id: u64,
+) -> AppResult<()> {
+    if operator_id == id {
+        return Err(AppError::BadRequest("不能删除当前登录用户".to_string()));
+    }
+    let res = soft_delete_user(rb, operator_id, id).await?;
+    if res.rows_affected == 0 {
+        return Err(AppError::NotFound("用户不存在".to_string()));
+    }
+    revocation_store.revoke_user(rb, id).await?;
+    info!("用户 {} 被 {} 删除", id, operator_id);
+    Ok(())
+}
+
+/// 重置用户密码，并作废该用户已签发的全部令牌
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `revocation_store` - 令牌作废存储
+/// * `operator_id` - 当前操作人ID
+/// * `id` - 被重置密码的用户ID
+/// * `dto` - 新密码
+pub async fn reset_password(
+    rb: &RBatis,
+    revocation_store: &TokenRevocationStore,
+    operator_id: u64,
+    id: u64,
+    dto: ResetPasswordDto,
+) -> AppResult<()> {
+    let user = find_user(rb, id).await?;
+    password_policy_service::validate_password(
+        &CFG.password_policy,
+        &CFG.password_hash,
+        "password",
+        user.username.as_deref().unwrap_or_default(),
+        &dto.password,
+    )?;
+    update_password(rb, operator_id, id, &dto.password).await?;
+    revocation_store.revoke_user(rb, id).await?;
+    info!("用户 {} 的密码被 {} 重置", id, operator_id);
+    Ok(())
+}
+
+/// 加密保存用户的新密码
+///
+/// 只负责保存，调用方需先按密码策略校验，保存后再调用`revoke_user`作废已签发的令牌
+///
+/// # 参数
+/// * `rb` - 数据库连接实例
+/// * `operator_id` - 当前操作人ID
+/// * `id` - 用户ID
+/// * `password` - 明文新密码
+pub async fn update_password(
+    rb: &RBatis,
+    operator_id: u64,
+    id: u64,
+    password: &str,
+) -> AppResult<()> {
+    let encrypted_password = hash_password(password).await?;
+    let user = User {
+        password: Some(encrypted_password),
+        ..updated_by(operator_id)
+    };
+    update_by_id(rb, &user, id).await
+}
+
+// 查询未删除的用户，不存在时返回NotFound
+pub(crate) async fn find_user(rb: &RBatis, id: u64) -> AppResult<User> {
+    User::select_by_id(rb, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("用户不存在".to_string()))
+}
+
+// 仅包含更新人与更新时间的用户，其余字段为空时不会被更新
+pub(crate) fn updated_by(operator_id: u64) -> User {
+    User {
+        update_time: Some(DateTime::now()),
+        updater_id: Some(operator_id),
+        ..Default::default()
+    }
+}
+
+pub(crate) async fn update_by_id(rb: &RBatis, user: &User, id: u64) -> AppResult<()> {
+    User::update_by_map(rb, user, value! {"id": id, "is_delete": NOT_DELETED}).await?;
+    Ok(())
+}
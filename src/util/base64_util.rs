@@ -7,7 +7,7 @@ use rand::thread_rng;
 pub fn generate_random_base64_value() -> String {
     let rng = &mut thread_rng();
     let bytes = rng.r#gen::<[u8; 32]>();
-    general_purpose::STANDARD.encode(&bytes)
+    general_purpose::STANDARD.encode(bytes)
 }
 
 #[cfg(test)]
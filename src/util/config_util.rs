@@ -1,16 +1,25 @@
 use crate::model::config::Config;
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use lazy_static::lazy_static;
 use std::fs;
 
 impl Config {
     pub fn from_str(s: &str) -> Result<Config> {
-        toml::from_str(&s).with_context(|| "fail to parse config file!")
+        let config: Config = toml::from_str(s).with_context(|| "fail to parse config file!")?;
+        // 旧配置项单位为小时，新配置项单位为分钟，不自动换算，避免有效期被静默缩短
+        if let Some(hours) = config.jwt.expiration_time {
+            bail!(
+                "配置项 jwt.expiration_time 已废弃，请改为 jwt.access_expiration_time，单位为分钟（原配置 {} 小时应改为 {}）",
+                hours,
+                hours * 60
+            );
+        }
+        Ok(config)
     }
 
     pub fn from_file() -> Result<Config> {
         let config_str = fs::read_to_string("config.toml")
-            .with_context(|| format!("Failed to read the config file: config.toml"))?;
+            .with_context(|| "Failed to read the config file: config.toml")?;
         Config::from_str(&config_str).with_context(|| "Configuration is invalid")
     }
 }
@@ -28,4 +37,31 @@ mod test {
         let confg = Config::from_file().unwrap();
         assert_eq!("root", confg.database.user);
     }
+
+    #[test]
+    fn test_legacy_expiration_time() {
+        let config = r#"
+            [database]
+            user = "root"
+            password = ""
+            host = "localhost"
+            port = 3306
+            dbname = "test"
+
+            [jwt]
+            expiration_time = 24
+        "#;
+        let err = Config::from_str(config).err().unwrap();
+        assert!(err.to_string().contains("access_expiration_time"));
+        assert!(err.to_string().contains("1440"));
+
+        let config = config.replace("expiration_time = 24", "access_expiration_time = 45");
+        assert_eq!(
+            45,
+            Config::from_str(&config)
+                .unwrap()
+                .jwt
+                .access_expiration_time
+        );
+    }
 }
@@ -1,8 +1,7 @@
 use super::config_util::CFG;
-use crate::model::config::Database;
+use crate::model::{config::Database, error::AppResult};
 use rbatis::RBatis;
 use rbdc_mysql::MysqlDriver;
-use thiserror::Error;
 
 fn get_mysql_url() -> String {
     let Database {
@@ -15,16 +14,10 @@ fn get_mysql_url() -> String {
     format!("mysql://{}:{}@{}:{}/{}", user, password, host, port, dbname)
 }
 
-#[derive(Error, Debug)]
-pub enum DatabaseError {
-    #[error("Failed to initialize RBatis: {0}")]
-    InitError(#[from] rbatis::error::Error),
-}
-
 /**
  * 获取db实例
  */
-pub async fn get_db_instance() -> Result<RBatis, DatabaseError> {
+pub async fn get_db_instance() -> AppResult<RBatis> {
     let rb = RBatis::new();
     rb.init(MysqlDriver {}, &get_mysql_url())?;
     Ok(rb)
@@ -1,21 +1,116 @@
+use crate::model::config::{Auth, TokenSource};
+use crate::model::constance::{BEARER_PREFIX, REQUEST_HEAD_TOKEN};
 use crate::model::response::ResponseResult;
-use crate::util::token_util;
-use actix_web::{HttpRequest, HttpResponse};
+use crate::util::{config_util::CFG, token_util};
+use actix_web::{HttpRequest, HttpResponse, http::header::AUTHORIZATION, web};
+use std::collections::HashMap;
 
 pub fn get_header_value<'a>(req: &'a HttpRequest, header_name: &str) -> Option<&'a str> {
     req.headers().get(header_name).and_then(|v| v.to_str().ok())
 }
 
+/// 从请求中提取访问令牌
+///
+/// 按配置文件`[auth] token_sources`的顺序依次尝试，返回第一个非空的令牌
+pub fn extract_token(req: &HttpRequest) -> Option<String> {
+    extract_token_with(req, &CFG.auth)
+}
+
+/// 按指定的认证配置从请求中提取访问令牌
+pub fn extract_token_with(req: &HttpRequest, auth: &Auth) -> Option<String> {
+    auth.token_sources
+        .iter()
+        .find_map(|source| match source {
+            TokenSource::Bearer => get_header_value(req, AUTHORIZATION.as_str())
+                .and_then(|value| {
+                    value
+                        .get(..BEARER_PREFIX.len())
+                        .filter(|prefix| prefix.eq_ignore_ascii_case(BEARER_PREFIX))
+                        .map(|_| &value[BEARER_PREFIX.len()..])
+                })
+                .map(|token| token.trim().to_string()),
+            TokenSource::Header => {
+                get_header_value(req, REQUEST_HEAD_TOKEN).map(|token| token.trim().to_string())
+            }
+            TokenSource::Cookie => req
+                .cookie(&auth.token_cookie_name)
+                .map(|cookie| cookie.value().to_string()),
+            TokenSource::Query => {
+                web::Query::<HashMap<String, String>>::from_query(req.query_string())
+                    .ok()
+                    .and_then(|query| query.get(&auth.token_query_param).cloned())
+            }
+        })
+        .filter(|token| !token.is_empty())
+}
+
+/// 获取客户端IP
+///
+/// 默认使用TCP连接的对端地址；`trust_forwarded_for`为true时优先使用`Forwarded`、`X-Forwarded-For`请求头，
+/// 这些请求头可被客户端伪造，仅应在服务部署于可信代理之后时开启
+pub fn client_ip(req: &HttpRequest, trust_forwarded_for: bool) -> Option<String> {
+    if trust_forwarded_for {
+        req.connection_info()
+            .realip_remote_addr()
+            .map(str::to_string)
+    } else {
+        req.peer_addr().map(|addr| addr.ip().to_string())
+    }
+}
+
+#[allow(dead_code)]
 pub fn get_user_id_from_request(req: &HttpRequest) -> Result<u64, HttpResponse> {
-    let token =
-        get_header_value(req, crate::model::constance::REQUEST_HEAD_TOKEN).ok_or_else(|| {
-            HttpResponse::InternalServerError()
-                .json(ResponseResult::<bool>::fail("token is empty".to_string()))
-        })?;
+    let token = extract_token(req).ok_or_else(|| {
+        HttpResponse::InternalServerError()
+            .json(ResponseResult::<bool>::fail("token is empty".to_string()))
+    })?;
 
-    let user_id = token_util::get_user_id_by_token(token).map_err(|e| {
+    let user_id = token_util::get_user_id_by_token(&token).map_err(|e| {
         HttpResponse::InternalServerError().json(ResponseResult::<bool>::fail(e.to_string()))
     })?;
 
     Ok(user_id)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{cookie::Cookie, test::TestRequest};
+
+    #[test]
+    fn test_extract_token() {
+        let auth = Auth {
+            token_sources: vec![
+                TokenSource::Bearer,
+                TokenSource::Header,
+                TokenSource::Cookie,
+                TokenSource::Query,
+            ],
+            ..Default::default()
+        };
+
+        let req = TestRequest::default()
+            .insert_header((AUTHORIZATION, "bearer abc"))
+            .insert_header((REQUEST_HEAD_TOKEN, "legacy"))
+            .to_http_request();
+        assert_eq!(Some("abc".to_string()), extract_token_with(&req, &auth));
+
+        let req = TestRequest::default()
+            .insert_header((AUTHORIZATION, "Basic abc"))
+            .insert_header((REQUEST_HEAD_TOKEN, "legacy"))
+            .to_http_request();
+        assert_eq!(Some("legacy".to_string()), extract_token_with(&req, &auth));
+
+        let req = TestRequest::default()
+            .cookie(Cookie::new("token", "cookie"))
+            .to_http_request();
+        assert_eq!(Some("cookie".to_string()), extract_token_with(&req, &auth));
+
+        let req = TestRequest::with_uri("/test?token=query").to_http_request();
+        assert_eq!(Some("query".to_string()), extract_token_with(&req, &auth));
+
+        // 未启用的来源不会被读取
+        let req = TestRequest::with_uri("/test?token=query").to_http_request();
+        assert_eq!(None, extract_token_with(&req, &Auth::default()));
+    }
+}
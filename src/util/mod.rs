@@ -2,4 +2,7 @@ pub mod base64_util;
 pub mod config_util;
 pub mod db_util;
 pub mod header_util;
+pub mod jwt_key_util;
+pub mod password_util;
 pub mod token_util;
+pub mod totp_util;
@@ -0,0 +1,255 @@
+use crate::model::config::{PasswordHashAlgorithm, PasswordHashing};
+use crate::model::error::{AppError, AppResult};
+use crate::util::config_util::CFG;
+use actix_web::web;
+use argon2::password_hash::{PasswordHash, PasswordHasher as _, PasswordVerifier as _, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use lazy_static::lazy_static;
+use rand::Rng;
+use rand::thread_rng;
+use std::str::FromStr;
+
+/// 密码哈希算法
+///
+/// 每种实现只识别并校验自己格式的哈希；校验时使用哈希中记录的参数，
+/// 因此修改配置后旧哈希仍可校验，并可通过`needs_rehash`判断是否需要按新参数重新计算
+pub trait PasswordHasher: Send + Sync {
+    /// 计算密码哈希
+    fn hash(&self, password: &str) -> AppResult<String>;
+
+    /// 校验密码，哈希格式错误时返回错误
+    fn verify(&self, password: &str, hash: &str) -> AppResult<bool>;
+
+    /// 哈希是否为本算法的格式
+    fn recognizes(&self, hash: &str) -> bool;
+
+    /// 本算法格式的哈希是否使用了与当前配置不同的参数
+    fn needs_rehash(&self, hash: &str) -> bool;
+}
+
+/// bcrypt
+pub struct BcryptHasher {
+    cost: u32,
+}
+
+impl BcryptHasher {
+    pub fn new(cost: u32) -> Self {
+        BcryptHasher { cost }
+    }
+}
+
+impl PasswordHasher for BcryptHasher {
+    fn hash(&self, password: &str) -> AppResult<String> {
+        bcrypt::hash(password, self.cost)
+            .map_err(|err| AppError::Internal(format!("加密密码失败: {}", err)))
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> AppResult<bool> {
+        bcrypt::verify(password, hash)
+            .map_err(|err| AppError::Internal(format!("密码哈希无效: {}", err)))
+    }
+
+    fn recognizes(&self, hash: &str) -> bool {
+        ["$2a$", "$2b$", "$2x$", "$2y$"]
+            .iter()
+            .any(|prefix| hash.starts_with(prefix))
+    }
+
+    fn needs_rehash(&self, hash: &str) -> bool {
+        bcrypt::HashParts::from_str(hash).is_ok_and(|parts| parts.get_cost() != self.cost)
+    }
+}
+
+/// Argon2id
+pub struct Argon2idHasher {
+    params: Params,
+}
+
+impl Argon2idHasher {
+    pub fn new(memory_kib: u32, iterations: u32, parallelism: u32) -> AppResult<Self> {
+        let params = Params::new(memory_kib, iterations, parallelism, None)
+            .map_err(|err| AppError::Internal(format!("Argon2参数无效: {}", err)))?;
+        Ok(Argon2idHasher { params })
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+}
+
+impl PasswordHasher for Argon2idHasher {
+    fn hash(&self, password: &str) -> AppResult<String> {
+        let salt = SaltString::encode_b64(&thread_rng().r#gen::<[u8; 16]>())
+            .map_err(|err| AppError::Internal(format!("生成盐值失败: {}", err)))?;
+        self.argon2()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|err| AppError::Internal(format!("加密密码失败: {}", err)))
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> AppResult<bool> {
+        let parsed = PasswordHash::new(hash)
+            .map_err(|err| AppError::Internal(format!("密码哈希无效: {}", err)))?;
+        // 使用哈希中记录的算法、版本与参数校验
+        Ok(self
+            .argon2()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok())
+    }
+
+    fn recognizes(&self, hash: &str) -> bool {
+        hash.starts_with("$argon2")
+    }
+
+    fn needs_rehash(&self, hash: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return false;
+        };
+        let outdated_params = Params::try_from(&parsed).is_ok_and(|params| {
+            params.m_cost() != self.params.m_cost()
+                || params.t_cost() != self.params.t_cost()
+                || params.p_cost() != self.params.p_cost()
+        });
+        parsed.algorithm != Algorithm::Argon2id.ident()
+            || parsed.version != Some(Version::V0x13.into())
+            || outdated_params
+    }
+}
+
+/// 按配置选择算法的密码哈希服务
+///
+/// 新密码使用配置的算法，已有的哈希按其格式选择算法校验
+pub struct PasswordHashService {
+    // 配置的算法
+    current: Box<dyn PasswordHasher>,
+    // 其余支持校验的算法
+    others: Vec<Box<dyn PasswordHasher>>,
+}
+
+impl PasswordHashService {
+    pub fn from_config(config: &PasswordHashing) -> AppResult<Self> {
+        let bcrypt: Box<dyn PasswordHasher> = Box::new(BcryptHasher::new(config.bcrypt_cost));
+        let argon2: Box<dyn PasswordHasher> = Box::new(Argon2idHasher::new(
+            config.argon2_memory_kib,
+            config.argon2_iterations,
+            config.argon2_parallelism,
+        )?);
+        let (current, other) = match config.algorithm {
+            PasswordHashAlgorithm::Bcrypt => (bcrypt, argon2),
+            PasswordHashAlgorithm::Argon2id => (argon2, bcrypt),
+        };
+        Ok(PasswordHashService {
+            current,
+            others: vec![other],
+        })
+    }
+
+    /// 使用配置的算法计算密码哈希
+    pub fn hash(&self, password: &str) -> AppResult<String> {
+        self.current.hash(password)
+    }
+
+    /// 按哈希的格式选择算法校验密码，无法识别的格式返回错误
+    pub fn verify(&self, password: &str, hash: &str) -> AppResult<bool> {
+        self.hasher_of(hash)
+            .ok_or_else(|| AppError::Internal("无法识别的密码哈希格式".to_string()))?
+            .verify(password, hash)
+    }
+
+    /// 哈希是否需要按配置的算法与参数重新计算
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        !self.current.recognizes(hash) || self.current.needs_rehash(hash)
+    }
+
+    fn hasher_of(&self, hash: &str) -> Option<&dyn PasswordHasher> {
+        std::iter::once(&self.current)
+            .chain(self.others.iter())
+            .find(|hasher| hasher.recognizes(hash))
+            .map(|hasher| hasher.as_ref())
+    }
+}
+
+lazy_static! {
+    pub static ref PASSWORD_HASHER: PasswordHashService =
+        PasswordHashService::from_config(&CFG.password_hash).unwrap();
+}
+
+/// 在阻塞线程池中计算密码哈希，避免占用异步工作线程
+pub async fn hash_password(password: &str) -> AppResult<String> {
+    let password = password.to_string();
+    web::block(move || PASSWORD_HASHER.hash(&password))
+        .await
+        .map_err(|err| AppError::Internal(format!("加密密码失败: {}", err)))?
+}
+
+/// 在阻塞线程池中校验密码
+pub async fn verify_password(password: &str, hash: &str) -> AppResult<bool> {
+    let (password, hash) = (password.to_string(), hash.to_string());
+    web::block(move || PASSWORD_HASHER.verify(&password, &hash))
+        .await
+        .map_err(|err| AppError::Internal(format!("校验密码失败: {}", err)))?
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 测试使用最低强度的参数
+    fn service(algorithm: PasswordHashAlgorithm) -> PasswordHashService {
+        PasswordHashService::from_config(&PasswordHashing {
+            algorithm,
+            bcrypt_cost: 4,
+            argon2_memory_kib: 1024,
+            argon2_iterations: 1,
+            argon2_parallelism: 1,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_hash_and_verify() {
+        for algorithm in [
+            PasswordHashAlgorithm::Bcrypt,
+            PasswordHashAlgorithm::Argon2id,
+        ] {
+            let service = service(algorithm);
+            let hash = service.hash("Correct-Horse-7").unwrap();
+            assert!(service.verify("Correct-Horse-7", &hash).unwrap());
+            assert!(!service.verify("wrong", &hash).unwrap());
+            assert!(!service.needs_rehash(&hash));
+        }
+        assert!(
+            service(PasswordHashAlgorithm::Bcrypt)
+                .verify("x", "plain")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_needs_rehash() {
+        let bcrypt = service(PasswordHashAlgorithm::Bcrypt);
+        let argon2 = service(PasswordHashAlgorithm::Argon2id);
+        let bcrypt_hash = bcrypt.hash("Correct-Horse-7").unwrap();
+        let argon2_hash = argon2.hash("Correct-Horse-7").unwrap();
+
+        // 切换算法后旧哈希仍可校验，但需要重新计算
+        assert!(argon2.verify("Correct-Horse-7", &bcrypt_hash).unwrap());
+        assert!(argon2.needs_rehash(&bcrypt_hash));
+        assert!(bcrypt.verify("Correct-Horse-7", &argon2_hash).unwrap());
+        assert!(bcrypt.needs_rehash(&argon2_hash));
+
+        // 参数变化后需要重新计算
+        let stronger = |algorithm| {
+            PasswordHashService::from_config(&PasswordHashing {
+                algorithm,
+                bcrypt_cost: 5,
+                argon2_memory_kib: 2048,
+                argon2_iterations: 1,
+                argon2_parallelism: 1,
+            })
+            .unwrap()
+        };
+        assert!(stronger(PasswordHashAlgorithm::Bcrypt).needs_rehash(&bcrypt_hash));
+        assert!(stronger(PasswordHashAlgorithm::Argon2id).needs_rehash(&argon2_hash));
+    }
+}
@@ -1,14 +1,64 @@
-use crate::util::config_util::CFG;
-use jsonwebtoken::Algorithm;
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
-use serde::{Deserialize, Serialize};
+use crate::model::constance::TOTP_CHALLENGE_PURPOSE;
+use crate::util::jwt_key_util::JWT_KEY_RING;
+use base64::{Engine as _, engine::general_purpose};
+use chrono::Utc;
+use jsonwebtoken::{Header, Validation, decode, decode_header, encode};
+use rand::Rng;
+use rand::thread_rng;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TokenClaims {
     pub user_id: u64,
     pub username: String,
     pub exp: usize,
+    pub iat: usize,
+    pub jti: String,
     pub roles: Vec<String>,
+    // 签发时用户的权限版本号，低于用户当前版本号的令牌视为已失效
+    #[serde(default)]
+    pub auth_version: u64,
+}
+
+/// 两步验证的登录挑战令牌声明
+///
+/// 密码校验通过后签发，只能用于提交动态验证码。字段与`TokenClaims`不兼容，两种令牌不能互相冒用
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChallengeClaims {
+    pub user_id: u64,
+    pub username: String,
+    pub exp: usize,
+    pub iat: usize,
+    // 令牌用途，固定为 totp
+    pub purpose: String,
+}
+
+// 生成登录挑战令牌
+pub fn generate_challenge_token(
+    user_id: u64,
+    username: &str,
+    expiration_time: usize,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let claims = ChallengeClaims {
+        user_id,
+        username: username.to_string(),
+        exp: expiration_time,
+        iat: Utc::now().timestamp() as usize,
+        purpose: TOTP_CHALLENGE_PURPOSE.to_string(),
+    };
+    encode_with_active_key(&claims)
+}
+
+// 验证登录挑战令牌
+pub fn validate_challenge_token(
+    token: &str,
+) -> Result<ChallengeClaims, jsonwebtoken::errors::Error> {
+    let claims = decode_with_key_ring::<ChallengeClaims>(token)?;
+    if claims.purpose != TOTP_CHALLENGE_PURPOSE {
+        return Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into());
+    }
+    Ok(claims)
 }
 
 // 生成一个 JWT token
@@ -16,36 +66,108 @@ pub fn generate_jwt_token(
     user_id: u64,
     username: &str,
     expiration_time: usize,
-    roles: &Vec<String>,
+    roles: &[String],
+    auth_version: u64,
 ) -> Result<String, jsonwebtoken::errors::Error> {
     let claims = TokenClaims {
-        user_id: user_id,
+        user_id,
         username: username.to_string(),
         exp: expiration_time,
-        roles: roles.clone(),
+        iat: Utc::now().timestamp() as usize,
+        jti: generate_opaque_token(),
+        roles: roles.to_vec(),
+        auth_version,
     };
-    let header = Header::new(Algorithm::HS512);
-    encode(
-        &header,
-        &claims,
-        &EncodingKey::from_secret(CFG.jwt.token_secret.as_ref()),
-    )
+    encode_with_active_key(&claims)
 }
 
 // 验证 JWT token
 pub fn validate_jwt_token(token: &str) -> Result<TokenClaims, jsonwebtoken::errors::Error> {
-    let decoding_key = DecodingKey::from_secret(CFG.jwt.token_secret.as_ref());
-    // Algorithm::HS256 指定算法，根据实际使用的算法进行调整
-    let mut validation = Validation::new(Algorithm::HS512);
+    decode_with_key_ring::<TokenClaims>(token)
+}
+
+// 使用密钥环中的签发密钥签名，并在头部写入密钥ID
+fn encode_with_active_key<T: Serialize>(claims: &T) -> Result<String, jsonwebtoken::errors::Error> {
+    let key = JWT_KEY_RING.active_key();
+    let mut header = Header::new(key.algorithm);
+    header.kid = Some(key.kid.clone());
+    let encoding_key = key
+        .encoding_key
+        .as_ref()
+        .ok_or(jsonwebtoken::errors::ErrorKind::InvalidKeyFormat)?;
+    encode(&header, claims, encoding_key)
+}
+
+// 按头部的密钥ID选择验签密钥并校验签名与过期时间
+fn decode_with_key_ring<T: DeserializeOwned>(
+    token: &str,
+) -> Result<T, jsonwebtoken::errors::Error> {
+    let header = decode_header(token)?;
+    let key = JWT_KEY_RING
+        .find(header.kid.as_deref())
+        .ok_or(jsonwebtoken::errors::ErrorKind::InvalidToken)?;
+    // 只接受该密钥配置的签名算法
+    let mut validation = Validation::new(key.algorithm);
     // 启用过期时间验证
     validation.validate_exp = true;
 
-    let result = decode::<TokenClaims>(token, &decoding_key, &validation)?;
+    let result = decode::<T>(token, &key.decoding_key, &validation)?;
     Ok(result.claims)
 }
 
 // 从token中获取用户ID
+#[allow(dead_code)]
 pub fn get_user_id_by_token(token: &str) -> Result<u64, jsonwebtoken::errors::Error> {
     let claims = validate_jwt_token(token)?;
     Ok(claims.user_id)
 }
+
+// 生成一个不透明的随机令牌，用作刷新令牌
+pub fn generate_opaque_token() -> String {
+    let rng = &mut thread_rng();
+    let bytes = rng.r#gen::<[u8; 32]>();
+    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// 计算不透明令牌的摘要，数据库中只保存摘要
+pub fn hash_opaque_token(token: &str) -> String {
+    let digest = Sha256::digest(token.as_bytes());
+    general_purpose::URL_SAFE_NO_PAD.encode(digest)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_opaque_token() {
+        let token = generate_opaque_token();
+        assert_ne!(token, generate_opaque_token());
+        assert_eq!(hash_opaque_token(&token), hash_opaque_token(&token));
+        assert_ne!(token, hash_opaque_token(&token));
+    }
+
+    #[test]
+    fn test_challenge_token() {
+        let exp = Utc::now().timestamp() as usize + 60;
+        let challenge = generate_challenge_token(1, "alice", exp).unwrap();
+        let claims = validate_challenge_token(&challenge).unwrap();
+        assert_eq!((1, "alice"), (claims.user_id, claims.username.as_str()));
+        // 挑战令牌与访问令牌不能互相冒用
+        assert!(validate_jwt_token(&challenge).is_err());
+        let access = generate_jwt_token(1, "alice", exp, &[], 0).unwrap();
+        assert!(validate_challenge_token(&access).is_err());
+    }
+
+    #[test]
+    fn test_expired_token() {
+        // 超过默认的60秒时钟偏差后视为过期
+        let exp = Utc::now().timestamp() as usize - 120;
+        let token = generate_jwt_token(1, "alice", exp, &[], 0).unwrap();
+        let err = validate_jwt_token(&token).unwrap_err();
+        assert_eq!(
+            &jsonwebtoken::errors::ErrorKind::ExpiredSignature,
+            err.kind()
+        );
+    }
+}
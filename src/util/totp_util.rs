@@ -0,0 +1,159 @@
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use rand::Rng;
+use rand::thread_rng;
+use sha1::Sha1;
+
+// 时间步长，单位秒
+pub const TOTP_STEP_SECOND: u64 = 30;
+// 动态验证码位数
+pub const TOTP_DIGITS: u32 = 6;
+// 密钥长度，RFC 4226 推荐160位
+const TOTP_SECRET_BYTES: usize = 20;
+
+// 生成一个随机的TOTP密钥，返回无填充的Base32编码
+pub fn generate_totp_secret() -> String {
+    let secret = thread_rng().r#gen::<[u8; TOTP_SECRET_BYTES]>();
+    BASE32_NOPAD.encode(&secret)
+}
+
+// 生成一个恢复码，格式为 xxxx-xxxx，共40位随机数
+pub fn generate_recovery_code() -> String {
+    let code = BASE32_NOPAD
+        .encode(&thread_rng().r#gen::<[u8; 5]>())
+        .to_ascii_lowercase();
+    format!("{}-{}", &code[..4], &code[4..])
+}
+
+// 规范化用户输入的恢复码，忽略大小写、空白与连字符
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
+
+// 按 RFC 4226 计算HOTP验证码
+pub fn hotp(secret: &[u8], counter: u64, digits: u32) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC接受任意长度的密钥");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    // 动态截断
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    let code = binary as u64 % 10_u64.pow(digits);
+    format!("{:0width$}", code, width = digits as usize)
+}
+
+// 校验动态验证码，允许前后各 skew 个时间步的时钟偏差
+//
+// 校验通过时返回匹配的时间步，调用方据此拒绝重复使用同一时间步的验证码；
+// 密钥不是有效的Base32或验证码格式不正确时返回None
+pub fn verify_totp(secret: &str, code: &str, unix_time: u64, skew: u64) -> Option<u64> {
+    let code = code.trim();
+    if code.len() != TOTP_DIGITS as usize || !code.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let secret = decode_totp_secret(secret)?;
+    let current = unix_time / TOTP_STEP_SECOND;
+    (current.saturating_sub(skew)..=current + skew).find(|step| {
+        constant_time_eq(
+            hotp(&secret, *step, TOTP_DIGITS).as_bytes(),
+            code.as_bytes(),
+        )
+    })
+}
+
+// 生成认证器应用扫码使用的 otpauth:// URI
+pub fn otpauth_uri(issuer: &str, account: &str, secret: &str) -> String {
+    let issuer = utf8_percent_encode(issuer, NON_ALPHANUMERIC).to_string();
+    let account = utf8_percent_encode(account, NON_ALPHANUMERIC).to_string();
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={}&period={}",
+        TOTP_DIGITS, TOTP_STEP_SECOND
+    )
+}
+
+fn decode_totp_secret(secret: &str) -> Option<Vec<u8>> {
+    let secret = secret.trim().trim_end_matches('=').to_ascii_uppercase();
+    BASE32_NOPAD.decode(secret.as_bytes()).ok()
+}
+
+// 长度相同时比较耗时与内容无关
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 按 RFC 6238 计算指定时间的TOTP验证码
+    fn totp_at(secret: &[u8], unix_time: u64, digits: u32) -> String {
+        hotp(secret, unix_time / TOTP_STEP_SECOND, digits)
+    }
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        // RFC 6238 附录B中SHA1的测试向量
+        let secret = b"12345678901234567890";
+        let vectors = [
+            (59, "94287082"),
+            (1111111109, "07081804"),
+            (1111111111, "14050471"),
+            (1234567890, "89005924"),
+            (2000000000, "69279037"),
+            (20000000000, "65353130"),
+        ];
+        for (time, code) in vectors {
+            assert_eq!(code, totp_at(secret, time, 8));
+        }
+    }
+
+    #[test]
+    fn test_verify_totp() {
+        let secret = BASE32_NOPAD.encode(b"12345678901234567890");
+        // 59秒时的6位验证码为 287082，所在时间步为1
+        assert_eq!(Some(1), verify_totp(&secret, "287082", 59, 1));
+        // 允许一个时间步的偏差
+        assert_eq!(Some(1), verify_totp(&secret, "287082", 89, 1));
+        assert_eq!(None, verify_totp(&secret, "287082", 120, 1));
+        assert_eq!(None, verify_totp(&secret, "287082", 89, 0));
+        // 格式错误
+        assert_eq!(None, verify_totp(&secret, "28708", 59, 1));
+        assert_eq!(None, verify_totp("not base32!", "287082", 59, 1));
+        // 密钥大小写与填充不影响校验
+        let padded = format!("{}====", secret.to_lowercase());
+        assert_eq!(Some(1), verify_totp(&padded, " 287082 ", 59, 1));
+    }
+
+    #[test]
+    fn test_recovery_code() {
+        let code = generate_recovery_code();
+        assert_eq!(9, code.len());
+        assert_eq!(Some(4), code.find('-'));
+        assert_eq!(
+            normalize_recovery_code(&code),
+            normalize_recovery_code(&format!(" {} ", code.to_uppercase().replace('-', "")))
+        );
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        let secret = generate_totp_secret();
+        assert_eq!(32, secret.len());
+        assert_eq!(
+            format!(
+                "otpauth://totp/My%20App:alice%40example?secret={}&issuer=My%20App&algorithm=SHA1&digits=6&period=30",
+                secret
+            ),
+            otpauth_uri("My App", "alice@example", &secret)
+        );
+    }
+}